@@ -13,11 +13,10 @@ use safe_core::MockRouting as Routing;
 
 use crate::errors::AppError;
 use crate::{AppContext, AppMsgTx};
-use lru_cache::LruCache;
 use routing::{Authority, FullId, XorName};
 use rust_sodium::crypto::{box_, sign};
 use safe_core::client::{
-    setup_routing, spawn_routing_thread, ClientInner, IMMUT_DATA_CACHE_SIZE, REQUEST_TIMEOUT_SECS,
+    setup_routing, spawn_routing_thread, ClientConfig, ClientInner, REQUEST_TIMEOUT_SECS,
 };
 use safe_core::crypto::{shared_box, shared_secretbox, shared_sign};
 use safe_core::ipc::BootstrapConfig;
@@ -45,6 +44,18 @@ impl AppClient {
         core_tx: AppMsgTx,
         net_tx: NetworkTx,
         config: Option<BootstrapConfig>,
+    ) -> Result<Self, AppError> {
+        Self::unregistered_with_config(el_handle, core_tx, net_tx, config, ClientConfig::default())
+    }
+
+    /// Same as `unregistered`, but with the given client configuration instead of the default
+    /// one.
+    pub(crate) fn unregistered_with_config(
+        el_handle: Handle,
+        core_tx: AppMsgTx,
+        net_tx: NetworkTx,
+        config: Option<BootstrapConfig>,
+        client_config: ClientConfig,
     ) -> Result<Self, AppError> {
         trace!("Creating unregistered client.");
 
@@ -56,7 +67,7 @@ impl AppClient {
                 el_handle,
                 routing,
                 HashMap::with_capacity(10),
-                LruCache::new(IMMUT_DATA_CACHE_SIZE),
+                client_config,
                 Duration::from_secs(REQUEST_TIMEOUT_SECS),
                 joiner,
                 core_tx,
@@ -76,9 +87,37 @@ impl AppClient {
         net_tx: NetworkTx,
         config: BootstrapConfig,
     ) -> Result<Self, AppError> {
-        Self::from_keys_impl(keys, owner, el_handle, core_tx, net_tx, config, |routing| {
-            routing
-        })
+        Self::from_keys_with_config(
+            keys,
+            owner,
+            el_handle,
+            core_tx,
+            net_tx,
+            config,
+            ClientConfig::default(),
+        )
+    }
+
+    /// Same as `from_keys`, but with the given client configuration instead of the default one.
+    pub(crate) fn from_keys_with_config(
+        keys: ClientKeys,
+        owner: sign::PublicKey,
+        el_handle: Handle,
+        core_tx: AppMsgTx,
+        net_tx: NetworkTx,
+        config: BootstrapConfig,
+        client_config: ClientConfig,
+    ) -> Result<Self, AppError> {
+        Self::from_keys_impl(
+            keys,
+            owner,
+            el_handle,
+            core_tx,
+            net_tx,
+            config,
+            client_config,
+            |routing| routing,
+        )
     }
 
     /// Allows customising the mock Routing client before logging in using client keys.
@@ -105,6 +144,7 @@ impl AppClient {
             core_tx,
             net_tx,
             config,
+            ClientConfig::default(),
             routing_wrapper_fn,
         )
     }
@@ -116,6 +156,7 @@ impl AppClient {
         core_tx: AppMsgTx,
         net_tx: NetworkTx,
         config: BootstrapConfig,
+        client_config: ClientConfig,
         routing_wrapper_fn: F,
     ) -> Result<Self, AppError>
     where
@@ -135,7 +176,7 @@ impl AppClient {
                 el_handle,
                 routing,
                 HashMap::with_capacity(10),
-                LruCache::new(IMMUT_DATA_CACHE_SIZE),
+                client_config,
                 Duration::from_secs(REQUEST_TIMEOUT_SECS),
                 joiner,
                 core_tx,
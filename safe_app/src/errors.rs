@@ -46,6 +46,9 @@ mod codes {
     pub const ERR_REQUEST_TIMEOUT: i32 = -17;
     pub const ERR_CONFIG_FILE: i32 = -18;
     pub const ERR_IO: i32 = -19;
+    pub const ERR_REQUEST_CANCELLED: i32 = -20;
+    pub const ERR_INSUFFICIENT_BALANCE: i32 = -21;
+    pub const ERR_CONNECTION_LOST: i32 = -22;
 
     // routing Client errors
     pub const ERR_ACCESS_DENIED: i32 = -100;
@@ -413,6 +416,9 @@ fn core_error_code(err: &CoreError) -> i32 {
         CoreError::RequestTimeout => ERR_REQUEST_TIMEOUT,
         CoreError::ConfigError(_) => ERR_CONFIG_FILE,
         CoreError::IoError(_) => ERR_IO,
+        CoreError::RequestCancelled => ERR_REQUEST_CANCELLED,
+        CoreError::InsufficientBalance { .. } => ERR_INSUFFICIENT_BALANCE,
+        CoreError::ConnectionLost => ERR_CONNECTION_LOST,
         CoreError::Unexpected(_) => ERR_UNEXPECTED,
     }
 }
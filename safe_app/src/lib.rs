@@ -139,7 +139,7 @@ use safe_core::ipc::resp::{access_container_enc_key, AccessContainerEntry};
 use safe_core::ipc::{AccessContInfo, AppKeys, AuthGranted, BootstrapConfig};
 #[cfg(feature = "mock-network")]
 use safe_core::MockRouting as Routing;
-use safe_core::{event_loop, CoreMsg, CoreMsgTx, NetworkEvent, NetworkTx};
+use safe_core::{event_loop, ClientConfig, CoreMsg, CoreMsgTx, NetworkEvent, NetworkTx};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
@@ -193,6 +193,23 @@ impl App {
         })
     }
 
+    /// Create unregistered app with the given client configuration instead of the default one.
+    pub fn unregistered_with_config<N>(
+        disconnect_notifier: N,
+        config: Option<BootstrapConfig>,
+        client_config: ClientConfig,
+    ) -> Result<Self, AppError>
+    where
+        N: FnMut() + Send + 'static,
+    {
+        Self::new(disconnect_notifier, move |el_h, core_tx, net_tx| {
+            let client =
+                AppClient::unregistered_with_config(el_h, core_tx, net_tx, config, client_config)?;
+            let context = AppContext::unregistered();
+            Ok((client, context))
+        })
+    }
+
     /// Create registered app.
     pub fn registered<N>(
         app_id: String,
@@ -250,6 +267,54 @@ impl App {
         })
     }
 
+    /// Create registered app with the given client configuration instead of the default one.
+    pub fn registered_with_config<N>(
+        app_id: String,
+        auth_granted: AuthGranted,
+        disconnect_notifier: N,
+        client_config: ClientConfig,
+    ) -> Result<Self, AppError>
+    where
+        N: FnMut() + Send + 'static,
+    {
+        let AuthGranted {
+            app_keys:
+                AppKeys {
+                    owner_key,
+                    enc_key,
+                    enc_pk,
+                    enc_sk,
+                    sign_pk,
+                    sign_sk,
+                },
+            access_container_info,
+            bootstrap_config,
+            ..
+        } = auth_granted;
+
+        let client_keys = ClientKeys {
+            sign_pk,
+            sign_sk,
+            enc_pk,
+            enc_sk,
+            enc_key: enc_key.clone(),
+        };
+
+        Self::new(disconnect_notifier, move |el_h, core_tx, net_tx| {
+            let client = AppClient::from_keys_with_config(
+                client_keys,
+                owner_key,
+                el_h,
+                core_tx,
+                net_tx,
+                bootstrap_config,
+                client_config,
+            )?;
+            let context = AppContext::registered(app_id, enc_key, access_container_info);
+            Ok((client, context))
+        })
+    }
+
     /// Allows customising the mock Routing client before registering a new account.
     #[cfg(feature = "mock-network")]
     pub fn registered_with_hook<N, F>(
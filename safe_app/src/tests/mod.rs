@@ -23,6 +23,7 @@ use safe_authenticator::Authenticator;
 use safe_core::ffi::AccountInfo;
 use safe_core::ipc::req::{AppExchangeInfo, AuthReq};
 use safe_core::ipc::Permission;
+use safe_core::Client;
 #[cfg(feature = "mock-network")]
 use safe_core::MockRouting;
 use std::collections::HashMap;
@@ -59,6 +60,22 @@ fn refresh_access_info() {
     }));
 }
 
+// Test that registered and unregistered apps report their client type correctly.
+#[test]
+fn client_registration_status() {
+    let app = unwrap!(App::unregistered(|| (), None));
+    unwrap!(run(&app, |client, _context| {
+        assert!(!client.is_registered());
+        Ok(())
+    }));
+
+    let app = unwrap!(create_app_by_req(&create_auth_req(None, None)));
+    unwrap!(run(&app, |client, _context| {
+        assert!(client.is_registered());
+        Ok(())
+    }));
+}
+
 // Test fetching containers that an app has access to.
 #[test]
 #[allow(unsafe_code)]
@@ -15,7 +15,6 @@ use crate::errors::AuthError;
 use crate::AuthFuture;
 use crate::AuthMsgTx;
 use futures::Future;
-use lru_cache::LruCache;
 use maidsafe_utilities::serialisation::{deserialise, serialise};
 use routing::{
     AccountPacket, Authority, BootstrapConfig, EntryAction, Event, FullId, MessageId, MutableData,
@@ -25,11 +24,12 @@ use rust_sodium::crypto::sign::Seed;
 use rust_sodium::crypto::{box_, sign};
 use safe_core::client::account::Account;
 use safe_core::client::{
-    setup_routing, spawn_routing_thread, ClientInner, IMMUT_DATA_CACHE_SIZE, REQUEST_TIMEOUT_SECS,
+    setup_routing, spawn_routing_thread, ClientConfig, ClientInner, REQUEST_TIMEOUT_SECS,
 };
 use safe_core::crypto::{shared_box, shared_secretbox, shared_sign};
 #[cfg(any(test, feature = "testing"))]
 use safe_core::utils::seed::{divide_seed, SEED_SUBPARTS};
+use safe_core::utils::CredentialStrength;
 use safe_core::{utils, Client, ClientKeys, CoreError, FutureExt, MDataInfo, NetworkTx};
 use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap};
@@ -55,6 +55,27 @@ impl AuthClient {
         el_handle: Handle,
         core_tx: AuthMsgTx,
         net_tx: NetworkTx,
+    ) -> Result<Self, AuthError> {
+        Self::registered_with_policy(
+            acc_locator,
+            acc_password,
+            invitation,
+            false,
+            el_handle,
+            core_tx,
+            net_tx,
+        )
+    }
+
+    /// Same as `registered`, but with the given client configuration instead of the default one.
+    pub(crate) fn registered_with_config(
+        acc_locator: &str,
+        acc_password: &str,
+        invitation: &str,
+        el_handle: Handle,
+        core_tx: AuthMsgTx,
+        net_tx: NetworkTx,
+        config: ClientConfig,
     ) -> Result<Self, AuthError> {
         Self::registered_impl(
             acc_locator.as_bytes(),
@@ -64,6 +85,38 @@ impl AuthClient {
             core_tx,
             net_tx,
             None,
+            config,
+            |routing| routing,
+        )
+    }
+
+    /// Same as `registered`, but if `require_strong` is set, credentials estimated to be `Weak` by
+    /// `utils::credential_strength` are rejected with `WeakCredentials` before anything is sent to
+    /// the network.
+    pub(crate) fn registered_with_policy(
+        acc_locator: &str,
+        acc_password: &str,
+        invitation: &str,
+        require_strong: bool,
+        el_handle: Handle,
+        core_tx: AuthMsgTx,
+        net_tx: NetworkTx,
+    ) -> Result<Self, AuthError> {
+        if require_strong
+            && utils::credential_strength(acc_locator, acc_password) == CredentialStrength::Weak
+        {
+            return Err(AuthError::WeakCredentials);
+        }
+
+        Self::registered_impl(
+            acc_locator.as_bytes(),
+            acc_password.as_bytes(),
+            invitation,
+            el_handle,
+            core_tx,
+            net_tx,
+            None,
+            ClientConfig::default(),
             |routing| routing,
         )
     }
@@ -94,6 +147,7 @@ where {
             core_tx,
             net_tx,
             Some(&id_seed),
+            ClientConfig::default(),
             |routing| routing,
         )
     }
@@ -120,6 +174,7 @@ where {
             core_tx,
             net_tx,
             None,
+            ClientConfig::default(),
             routing_wrapper_fn,
         )
     }
@@ -134,6 +189,7 @@ where {
         core_tx: AuthMsgTx,
         net_tx: NetworkTx,
         id_seed: Option<&Seed>,
+        config: ClientConfig,
         routing_wrapper_fn: F,
     ) -> Result<Self, AuthError>
     where
@@ -201,7 +257,7 @@ where {
                 el_handle,
                 routing,
                 HashMap::with_capacity(10),
-                LruCache::new(IMMUT_DATA_CACHE_SIZE),
+                config,
                 Duration::from_secs(REQUEST_TIMEOUT_SECS),
                 joiner,
                 core_tx,
@@ -225,6 +281,25 @@ where {
         el_handle: Handle,
         core_tx: AuthMsgTx,
         net_tx: NetworkTx,
+    ) -> Result<Self, AuthError> {
+        Self::login_with_config(
+            acc_locator,
+            acc_password,
+            el_handle,
+            core_tx,
+            net_tx,
+            ClientConfig::default(),
+        )
+    }
+
+    /// Same as `login`, but with the given client configuration instead of the default one.
+    pub(crate) fn login_with_config(
+        acc_locator: &str,
+        acc_password: &str,
+        el_handle: Handle,
+        core_tx: AuthMsgTx,
+        net_tx: NetworkTx,
+        config: ClientConfig,
     ) -> Result<Self, AuthError> {
         Self::login_impl(
             acc_locator.as_bytes(),
@@ -232,6 +307,7 @@ where {
             el_handle,
             core_tx,
             net_tx,
+            config,
             |routing| routing,
         )
     }
@@ -245,9 +321,15 @@ where {
         net_tx: NetworkTx,
     ) -> Result<Self, AuthError> {
         let arr = divide_seed(seed)?;
-        Self::login_impl(arr[0], arr[1], el_handle, core_tx, net_tx, |routing| {
-            routing
-        })
+        Self::login_impl(
+            arr[0],
+            arr[1],
+            el_handle,
+            core_tx,
+            net_tx,
+            ClientConfig::default(),
+            |routing| routing,
+        )
     }
 
     #[cfg(all(feature = "mock-network", any(test, feature = "testing")))]
@@ -269,6 +351,7 @@ where {
             el_handle,
             core_tx,
             net_tx,
+            ClientConfig::default(),
             routing_wrapper_fn,
         )
     }
@@ -279,6 +362,7 @@ where {
         el_handle: Handle,
         core_tx: AuthMsgTx,
         net_tx: NetworkTx,
+        config: ClientConfig,
         routing_wrapper_fn: F,
     ) -> Result<Self, AuthError>
     where
@@ -342,7 +426,7 @@ where {
                 el_handle,
                 routing,
                 HashMap::with_capacity(10),
-                LruCache::new(IMMUT_DATA_CACHE_SIZE),
+                config,
                 Duration::from_secs(REQUEST_TIMEOUT_SECS),
                 joiner,
                 core_tx,
@@ -429,13 +513,9 @@ where {
     pub fn update_account_packet(&self) -> Box<AuthFuture<()>> {
         trace!("Updating account packet.");
 
-        let entry_version = {
-            let mut auth_inner = self.auth_inner.borrow_mut();
-            auth_inner.session_packet_version += 1;
-            auth_inner.session_packet_version
-        };
-
         let auth_inner = self.auth_inner.borrow();
+        let entry_version = auth_inner.session_packet_version + 1;
+
         let update = {
             let account = &auth_inner.acc;
             let keys = &auth_inner.user_cred;
@@ -448,12 +528,84 @@ where {
         };
 
         let data_name = auth_inner.acc_loc;
+        let auth_inner = Rc::clone(&self.auth_inner);
 
         self.mutate_mdata_entries(data_name, TYPE_TAG_SESSION_PACKET, update)
+            .map(move |()| {
+                auth_inner.borrow_mut().session_packet_version = entry_version;
+            })
             .map_err(AuthError::from)
             .into_box()
     }
 
+    /// Returns the version of the account packet this client last loaded or stored successfully.
+    /// A newer version on the network means the packet has been modified concurrently.
+    pub fn session_packet_version(&self) -> u64 {
+        let auth_inner = self.auth_inner.borrow();
+        auth_inner.session_packet_version
+    }
+
+    /// Brings the in-memory account in line with the account packet stored on the network, e.g.
+    /// after another login updated the packet concurrently. The root directories and the packet
+    /// version are taken from the network copy, while the standard directories count as created
+    /// if either copy says so. If the network copy is missing that, it's re-stored.
+    /// Returns `true` if the two copies differed.
+    pub fn reconcile_account_packet(&self) -> Box<AuthFuture<bool>> {
+        trace!("Reconciling account packet.");
+
+        let client = self.clone();
+        let acc_loc = self.auth_inner.borrow().acc_loc;
+
+        self.get_mdata_value(
+            acc_loc,
+            TYPE_TAG_SESSION_PACKET,
+            ACC_LOGIN_ENTRY_KEY.to_owned(),
+        )
+        .map_err(AuthError::from)
+        .and_then(move |value| {
+            let network_acc = {
+                let auth_inner = client.auth_inner.borrow();
+                let keys = &auth_inner.user_cred;
+
+                match deserialise::<AccountPacket>(&value.content)? {
+                    AccountPacket::AccPkt(acc_content)
+                    | AccountPacket::WithInvitation {
+                        acc_pkt: acc_content,
+                        ..
+                    } => Account::decrypt(&acc_content, &keys.password, &keys.pin)?,
+                }
+            };
+
+            let (diverged, needs_store) = {
+                let mut auth_inner = client.auth_inner.borrow_mut();
+                auth_inner.session_packet_version = value.entry_version;
+
+                let account = &mut auth_inner.acc;
+                let diverged = *account != network_acc;
+                let needs_store = account.root_dirs_created && !network_acc.root_dirs_created;
+
+                account.access_container = network_acc.access_container;
+                account.config_root = network_acc.config_root;
+                account.root_dirs_created |= network_acc.root_dirs_created;
+
+                (diverged, needs_store)
+            };
+
+            Ok::<_, AuthError>((client, diverged, needs_store))
+        })
+        .and_then(|(client, diverged, needs_store)| {
+            if needs_store {
+                client
+                    .update_account_packet()
+                    .map(move |()| diverged)
+                    .into_box()
+            } else {
+                ok!(diverged)
+            }
+        })
+        .into_box()
+    }
+
     /// Returns the current status of std/root dirs creation.
     pub fn std_dirs_created(&self) -> bool {
         let auth_inner = self.auth_inner.borrow();
@@ -563,9 +715,10 @@ impl UserCred {
 mod tests {
     use super::*;
     use futures::sync::mpsc;
+    use futures::Stream;
     use routing::ClientError;
     use safe_core::utils::test_utils::{finish, setup_client};
-    use safe_core::{utils, CoreError, DIR_TAG};
+    use safe_core::{utils, ConnectionState, CoreError, NetworkEvent, DIR_TAG};
     use tokio_core::reactor::Core;
     use AuthMsgTx;
 
@@ -601,6 +754,49 @@ mod tests {
         }
     }
 
+    // Test that weak credentials are rejected when strong ones are required, while strong ones
+    // are accepted.
+    #[test]
+    fn registered_with_policy() {
+        let el = unwrap!(Core::new());
+        let (core_tx, _): (AuthMsgTx, _) = mpsc::unbounded();
+        let (net_tx, _) = mpsc::unbounded();
+
+        let inv = unwrap!(utils::generate_random_string(10));
+
+        match AuthClient::registered_with_policy(
+            "locator",
+            "password",
+            &inv,
+            true,
+            el.handle(),
+            core_tx.clone(),
+            net_tx.clone(),
+        ) {
+            Err(AuthError::WeakCredentials) => (),
+            x => panic!("Unexpected outcome: {:?}", x),
+        }
+
+        let sec_0 = format!(
+            "Locator-{}-Xy9!",
+            unwrap!(utils::generate_random_string(10))
+        );
+        let sec_1 = format!(
+            "Password-{}-Zq8?",
+            unwrap!(utils::generate_random_string(10))
+        );
+
+        let _ = unwrap!(AuthClient::registered_with_policy(
+            &sec_0,
+            &sec_1,
+            &inv,
+            true,
+            el.handle(),
+            core_tx,
+            net_tx,
+        ));
+    }
+
     // Test creating and logging in to an account on the network.
     #[test]
     fn login() {
@@ -635,6 +831,115 @@ mod tests {
         );
     }
 
+    // Test that the client configuration given on account creation and login is used.
+    #[test]
+    fn registered_and_login_with_config() {
+        let sec_0 = unwrap!(utils::generate_random_string(10));
+        let sec_1 = unwrap!(utils::generate_random_string(10));
+        let inv = unwrap!(utils::generate_random_string(10));
+
+        // Without the negative cache, every GET of missing data reaches the network.
+        let config = ClientConfig {
+            negative_cache_ttl: None,
+            ..ClientConfig::default()
+        };
+
+        // Return the number of GETs which reached the network for two GETs of missing data.
+        let check = |client: &AuthClient| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let name = rand::random();
+            let before = client.stats().idata_gets;
+
+            client
+                .get_idata(name)
+                .then(move |_| client2.get_idata(name))
+                .then(move |res| {
+                    match res {
+                        Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => (),
+                        x => panic!("Unexpected result: {:?}", x),
+                    }
+                    Ok::<_, AuthError>(client3.stats().idata_gets - before)
+                })
+        };
+
+        let gets = setup_client(
+            &(),
+            |el_h, core_tx, net_tx| {
+                AuthClient::registered_with_config(
+                    &sec_0, &sec_1, &inv, el_h, core_tx, net_tx, config,
+                )
+            },
+            check,
+        );
+        assert_eq!(gets, 2);
+
+        let gets = setup_client(
+            &(),
+            |el_h, core_tx, net_tx| {
+                AuthClient::login_with_config(&sec_0, &sec_1, el_h, core_tx, net_tx, config)
+            },
+            check,
+        );
+        assert_eq!(gets, 2);
+    }
+
+    // Test logging out and then logging in again within the same process.
+    #[test]
+    fn logout() {
+        let mut el = unwrap!(Core::new());
+        let (core_tx, _): (AuthMsgTx, _) = mpsc::unbounded();
+        let (net_tx, net_rx) = mpsc::unbounded();
+
+        let sec_0 = unwrap!(utils::generate_random_string(10));
+        let sec_1 = unwrap!(utils::generate_random_string(10));
+        let inv = unwrap!(utils::generate_random_string(10));
+
+        let client = unwrap!(AuthClient::registered(
+            &sec_0,
+            &sec_1,
+            &inv,
+            el.handle(),
+            core_tx.clone(),
+            net_tx.clone(),
+        ));
+
+        // Logging out through a clone disconnects all the clones sharing the connection.
+        let client2 = client.clone();
+        client2.logout();
+        assert_eq!(client.connection_state(), ConnectionState::LoggedOut);
+        match el.run(client.get_idata(rand::random())) {
+            Err(CoreError::OperationAborted) => (),
+            x => panic!("Unexpected result: {:?}", x),
+        }
+
+        // A logged out client can't be reconnected.
+        match client.restart_routing() {
+            Err(CoreError::OperationAborted) => (),
+            x => panic!("Unexpected result: {:?}", x),
+        }
+        assert_eq!(client.connection_state(), ConnectionState::LoggedOut);
+
+        let client = unwrap!(AuthClient::login(
+            &sec_0,
+            &sec_1,
+            el.handle(),
+            core_tx,
+            net_tx,
+        ));
+        client.logout();
+
+        // Each routing event loop reported the disconnection before its thread was joined.
+        let events = unwrap!(net_rx.take(2).collect().wait());
+        assert_eq!(events.len(), 2);
+        for event in events {
+            match event {
+                NetworkEvent::Disconnected => (),
+                x => panic!("Unexpected network event: {:?}", x),
+            }
+        }
+    }
+
     // Test logging in using a seeded account.
     #[test]
     fn seeded_login() {
@@ -752,6 +1057,105 @@ mod tests {
         );
     }
 
+    // Test that the session packet version is only bumped by successful updates.
+    #[cfg(feature = "mock-network")]
+    #[test]
+    fn session_packet_version() {
+        let sec_0 = unwrap!(utils::generate_random_string(10));
+        let sec_1 = unwrap!(utils::generate_random_string(10));
+        let inv = unwrap!(utils::generate_random_string(10));
+
+        setup_client(
+            &(),
+            |el_h, core_tx, net_tx| {
+                AuthClient::registered(&sec_0, &sec_1, &inv, el_h, core_tx, net_tx)
+            },
+            move |client| {
+                let client2 = client.clone();
+                let client3 = client.clone();
+
+                assert_eq!(client.session_packet_version(), 0);
+
+                client
+                    .update_account_packet()
+                    .then(move |res| {
+                        unwrap!(res);
+                        assert_eq!(client2.session_packet_version(), 1);
+
+                        client2.set_network_limits(Some(0));
+                        client2.update_account_packet()
+                    })
+                    .then(move |res| {
+                        assert!(res.is_err());
+                        assert_eq!(client3.session_packet_version(), 1);
+
+                        client3.set_network_limits(None);
+                        client3.update_account_packet()
+                    })
+            },
+        );
+
+        setup_client(
+            &(),
+            |el_h, core_tx, net_tx| AuthClient::login(&sec_0, &sec_1, el_h, core_tx, net_tx),
+            move |client| {
+                assert_eq!(client.session_packet_version(), 2);
+                finish()
+            },
+        );
+    }
+
+    // Test reconciling the in-memory account with the account packet on the network.
+    #[test]
+    fn account_packet_reconciliation() {
+        let sec_0 = unwrap!(utils::generate_random_string(10));
+        let sec_1 = unwrap!(utils::generate_random_string(10));
+        let inv = unwrap!(utils::generate_random_string(10));
+
+        setup_client(
+            &(),
+            |el_h, core_tx, net_tx| {
+                AuthClient::registered(&sec_0, &sec_1, &inv, el_h, core_tx, net_tx)
+            },
+            move |client| {
+                let client2 = client.clone();
+                let client3 = client.clone();
+                let client4 = client.clone();
+
+                let network_dir = client.config_root_dir();
+                assert!(!client.std_dirs_created());
+
+                // Diverge from the network copy without storing the changes.
+                assert!(client.set_config_root_dir(unwrap!(MDataInfo::random_private(DIR_TAG))));
+                client.set_std_dirs_created(true);
+
+                client
+                    .reconcile_account_packet()
+                    .then(move |res| {
+                        assert!(unwrap!(res));
+                        assert_eq!(client2.config_root_dir(), network_dir);
+                        assert!(client2.std_dirs_created());
+
+                        client3.reconcile_account_packet()
+                    })
+                    .then(move |res| {
+                        assert!(!unwrap!(res));
+                        // The packet version was kept in sync, so it can still be updated.
+                        client4.update_account_packet()
+                    })
+            },
+        );
+
+        setup_client(
+            &(),
+            |el_h, core_tx, net_tx| AuthClient::login(&sec_0, &sec_1, el_h, core_tx, net_tx),
+            move |client| {
+                assert!(client.std_dirs_created());
+                finish()
+            },
+        );
+    }
+
     // Test restarting routing after a network disconnect.
     #[cfg(feature = "mock-network")]
     #[test]
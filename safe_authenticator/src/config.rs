@@ -42,6 +42,9 @@ pub const KEY_APPS: &[u8] = b"apps";
 /// Config file key under which the revocation queue is stored.
 pub const KEY_APP_REVOCATION_QUEUE: &[u8] = b"revocation-queue";
 
+/// Prefix of the config file keys under which the apps' own configuration blobs are stored.
+pub const KEY_APP_CONFIG_PREFIX: &[u8] = b"app-config-";
+
 /// Maps from a SHA-3 hash of an app ID to app info.
 pub type Apps = HashMap<[u8; 32], AppInfo>;
 /// Contains a queue of revocations that are currently running or have failed.
@@ -192,6 +195,48 @@ pub fn repush_to_app_revocation_queue(
     )
 }
 
+/// Retrieves the configuration blob stored for the given app, if any.
+pub fn get_app_config(client: &AuthClient, app_id: &str) -> Box<AuthFuture<Option<Vec<u8>>>> {
+    get_entry(client, &app_config_key(app_id))
+        .map(|(_, config)| config)
+        .into_box()
+}
+
+/// Store the configuration blob for the given app, replacing any previous one. The entry version
+/// is managed internally, so concurrent updates are retried on top of the latest version.
+pub fn put_app_config(client: &AuthClient, app_id: &str, config: Vec<u8>) -> Box<AuthFuture<()>> {
+    trace!("Storing config for app with ID {}...", app_id);
+
+    let client = client.clone();
+    let key = app_config_key(app_id);
+
+    get_entry::<Option<Vec<u8>>>(&client, &key)
+        .and_then(move |(version, current)| {
+            mutate_entry(
+                &client,
+                &key,
+                current,
+                next_version(version),
+                move |current| {
+                    if current.as_ref() != Some(&config) {
+                        *current = Some(config.clone());
+                        true
+                    } else {
+                        false
+                    }
+                },
+            )
+        })
+        .map(|_| ())
+        .into_box()
+}
+
+fn app_config_key(app_id: &str) -> Vec<u8> {
+    let mut key = KEY_APP_CONFIG_PREFIX.to_vec();
+    key.extend_from_slice(&sha3_256(app_id.as_bytes()));
+    key
+}
+
 fn get_entry<T>(client: &AuthClient, key: &[u8]) -> Box<AuthFuture<(Option<u64>, T)>>
 where
     T: Default + DeserializeOwned + Serialize + 'static,
@@ -46,6 +46,9 @@ mod codes {
     pub const ERR_REQUEST_TIMEOUT: i32 = -17;
     pub const ERR_CONFIG_FILE: i32 = -18;
     pub const ERR_IO: i32 = -19;
+    pub const ERR_REQUEST_CANCELLED: i32 = -20;
+    pub const ERR_INSUFFICIENT_BALANCE: i32 = -21;
+    pub const ERR_CONNECTION_LOST: i32 = -22;
 
     // routing Client errors
     pub const ERR_ACCESS_DENIED: i32 = -100;
@@ -87,6 +90,7 @@ mod codes {
     pub const ERR_IO_ERROR: i32 = -1013;
     pub const ERR_ACCOUNT_CONTAINERS_CREATION: i32 = -1014;
     pub const ERR_NO_SUCH_CONTAINER: i32 = -1015;
+    pub const ERR_WEAK_CREDENTIALS: i32 = -1016;
     pub const ERR_UNEXPECTED: i32 = -2000;
 }
 
@@ -110,6 +114,8 @@ pub enum AuthError {
     AccountContainersCreation(String),
     /// Failure due to the attempted creation of an invalid container.
     NoSuchContainer(String),
+    /// Account credentials rejected for being too weak.
+    WeakCredentials,
 }
 
 impl Display for AuthError {
@@ -131,6 +137,7 @@ impl Display for AuthError {
             AuthError::NoSuchContainer(ref name) => {
                 write!(formatter, "'{}' not found in the access container", name)
             }
+            AuthError::WeakCredentials => write!(formatter, "Account credentials are too weak"),
         }
     }
 }
@@ -259,6 +266,7 @@ impl ErrorCode for AuthError {
             AuthError::IoError(_) => ERR_IO_ERROR,
             AuthError::AccountContainersCreation(_) => ERR_ACCOUNT_CONTAINERS_CREATION,
             AuthError::NoSuchContainer(_) => ERR_NO_SUCH_CONTAINER,
+            AuthError::WeakCredentials => ERR_WEAK_CREDENTIALS,
             AuthError::Unexpected(_) => ERR_UNEXPECTED,
         }
     }
@@ -305,6 +313,9 @@ fn core_error_code(err: &CoreError) -> i32 {
         CoreError::RequestTimeout => ERR_REQUEST_TIMEOUT,
         CoreError::ConfigError(_) => ERR_CONFIG_FILE,
         CoreError::IoError(_) => ERR_IO,
+        CoreError::RequestCancelled => ERR_REQUEST_CANCELLED,
+        CoreError::InsufficientBalance { .. } => ERR_INSUFFICIENT_BALANCE,
+        CoreError::ConnectionLost => ERR_CONNECTION_LOST,
         CoreError::Unexpected(_) => ERR_UNEXPECTED,
     }
 }
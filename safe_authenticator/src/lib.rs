@@ -110,7 +110,7 @@ use futures::{Future, IntoFuture};
 use maidsafe_utilities::thread::{self, Joiner};
 #[cfg(feature = "mock-network")]
 use safe_core::MockRouting;
-use safe_core::{event_loop, CoreMsg, CoreMsgTx, FutureExt, NetworkEvent, NetworkTx};
+use safe_core::{event_loop, ClientConfig, CoreMsg, CoreMsgTx, FutureExt, NetworkEvent, NetworkTx};
 use std::sync::mpsc as std_mpsc;
 use std::sync::mpsc::sync_channel;
 use std::sync::Mutex;
@@ -157,6 +157,22 @@ impl Authenticator {
         invitation: S,
         disconnect_notifier: N,
     ) -> Result<Self, AuthError>
+    where
+        N: FnMut() + Send + 'static,
+        S: Into<String>,
+    {
+        Self::create_acc_with_policy(locator, password, invitation, false, disconnect_notifier)
+    }
+
+    /// Same as `create_acc`, but if `require_strong` is set, credentials estimated to be `Weak` by
+    /// `safe_core::utils::credential_strength` are rejected with `AuthError::WeakCredentials`.
+    pub fn create_acc_with_policy<S, N>(
+        locator: S,
+        password: S,
+        invitation: S,
+        require_strong: bool,
+        disconnect_notifier: N,
+    ) -> Result<Self, AuthError>
     where
         N: FnMut() + Send + 'static,
         S: Into<String>,
@@ -167,7 +183,47 @@ impl Authenticator {
 
         Self::create_acc_impl(
             move |el_h, core_tx, net_tx| {
-                AuthClient::registered(&locator, &password, &invitation, el_h, core_tx, net_tx)
+                AuthClient::registered_with_policy(
+                    &locator,
+                    &password,
+                    &invitation,
+                    require_strong,
+                    el_h,
+                    core_tx,
+                    net_tx,
+                )
+            },
+            disconnect_notifier,
+        )
+    }
+
+    /// Same as `create_acc`, but with the given client configuration instead of the default one.
+    pub fn create_acc_with_config<S, N>(
+        locator: S,
+        password: S,
+        invitation: S,
+        config: ClientConfig,
+        disconnect_notifier: N,
+    ) -> Result<Self, AuthError>
+    where
+        N: FnMut() + Send + 'static,
+        S: Into<String>,
+    {
+        let locator = locator.into();
+        let password = password.into();
+        let invitation = invitation.into();
+
+        Self::create_acc_impl(
+            move |el_h, core_tx, net_tx| {
+                AuthClient::registered_with_config(
+                    &locator,
+                    &password,
+                    &invitation,
+                    el_h,
+                    core_tx,
+                    net_tx,
+                    config,
+                )
             },
             disconnect_notifier,
         )
@@ -255,6 +311,28 @@ impl Authenticator {
         )
     }
 
+    /// Same as `login`, but with the given client configuration instead of the default one.
+    pub fn login_with_config<S, N>(
+        locator: S,
+        password: S,
+        config: ClientConfig,
+        disconnect_notifier: N,
+    ) -> Result<Self, AuthError>
+    where
+        S: Into<String>,
+        N: FnMut() + Send + 'static,
+    {
+        let locator = locator.into();
+        let password = password.into();
+
+        Self::login_impl(
+            move |el_h, core_tx, net_tx| {
+                AuthClient::login_with_config(&locator, &password, el_h, core_tx, net_tx, config)
+            },
+            disconnect_notifier,
+        )
+    }
+
     /// Log in to an existing account.
     pub fn login_impl<F: Send + 'static, N>(
         create_client_fn: F,
@@ -468,6 +468,37 @@ fn config_root_dir() {
     assert!(config.content.is_empty());
 }
 
+// Test storing, replacing and retrieving per-app configuration blobs.
+#[test]
+fn app_config() {
+    let authenticator = test_utils::create_account_and_login();
+
+    unwrap!(run(&authenticator, |client| {
+        let client2 = client.clone();
+        let client3 = client.clone();
+        let client4 = client.clone();
+
+        config::get_app_config(client, "app")
+            .then(move |res| {
+                assert_eq!(unwrap!(res), None);
+                config::put_app_config(&client2, "app", vec![1, 2, 3])
+            })
+            .then(move |res| {
+                unwrap!(res);
+                config::put_app_config(&client3, "app", vec![4, 5])
+            })
+            .then(move |res| {
+                unwrap!(res);
+                config::get_app_config(&client4, "app")
+                    .join(config::get_app_config(&client4, "other-app"))
+            })
+            .map(|(config, other_config)| {
+                assert_eq!(config, Some(vec![4, 5]));
+                assert_eq!(other_config, None);
+            })
+    }));
+}
+
 // Test app authentication.
 #[test]
 fn app_authentication() {
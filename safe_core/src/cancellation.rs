@@ -0,0 +1,166 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::client::Client;
+use crate::errors::CoreError;
+use crate::event_loop::CoreFuture;
+use crate::utils::FutureExt;
+use futures::future::{self, Either};
+use futures::sync::oneshot;
+use futures::Future;
+use routing::MessageId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Token used to cancel a whole group of requests at once, e.g. all the chunk fetches making up a
+/// large value. Clones of the token share its state, so cancelling any of them cancels all the
+/// futures guarded by it.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Rc<RefCell<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    cancelled: bool,
+    waiters: Vec<oneshot::Sender<()>>,
+    // Requests still waiting for a response, with the function cancelling each of them.
+    requests: HashMap<MessageId, Box<Fn()>>,
+}
+
+impl CancellationToken {
+    /// Create a new token which hasn't been cancelled.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Cancel the token. All futures currently guarded by it fail with
+    /// `CoreError::RequestCancelled`, and so does anything guarded by it afterwards. The requests
+    /// guarded through `guard_request` are cancelled as well, so the client stops waiting for
+    /// their responses.
+    pub fn cancel(&self) {
+        let (waiters, requests) = {
+            let mut inner = self.inner.borrow_mut();
+            inner.cancelled = true;
+            (
+                inner.waiters.drain(..).collect::<Vec<_>>(),
+                inner.requests.drain().collect::<Vec<_>>(),
+            )
+        };
+
+        for (_, cancel_request) in requests {
+            cancel_request();
+        }
+
+        for waiter in waiters {
+            let _ = waiter.send(());
+        }
+    }
+
+    /// Return `true` if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.borrow().cancelled
+    }
+
+    /// Wrap `future` so that it fails with `CoreError::RequestCancelled` as soon as the token is
+    /// cancelled. Note that requests are sent as soon as their future is created, so callers
+    /// wanting to avoid issuing a request at all should check `is_cancelled` first.
+    pub fn guard<F>(&self, future: F) -> Box<CoreFuture<F::Item>>
+    where
+        F: Future<Error = CoreError> + 'static,
+    {
+        if self.is_cancelled() {
+            return future::err(CoreError::RequestCancelled).into_box();
+        }
+
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut inner = self.inner.borrow_mut();
+            // Drop the waiters of futures which have already completed.
+            inner.waiters.retain(|waiter| !waiter.is_canceled());
+            inner.waiters.push(tx);
+        }
+
+        // If all the clones of the token are dropped, it can't be cancelled any more.
+        let cancelled = rx.then(|res| match res {
+            Ok(()) => Either::A(future::err(CoreError::RequestCancelled)),
+            Err(_) => Either::B(future::empty()),
+        });
+
+        future
+            .select(cancelled)
+            .map(|(item, _)| item)
+            .map_err(|(error, _)| error)
+            .into_box()
+    }
+
+    /// Same as `guard`, but `future` is the response to the request sent through `client` under
+    /// `msg_id`. Cancelling the token also cancels the request via `Client::cancel_request`.
+    pub fn guard_request<C, F>(
+        &self,
+        client: &C,
+        msg_id: MessageId,
+        future: F,
+    ) -> Box<CoreFuture<F::Item>>
+    where
+        C: Client,
+        F: Future<Error = CoreError> + 'static,
+    {
+        if self.is_cancelled() {
+            let _ = client.cancel_request(&msg_id);
+            return future::err(CoreError::RequestCancelled).into_box();
+        }
+
+        let client = client.clone();
+        let _ = self.inner.borrow_mut().requests.insert(
+            msg_id,
+            Box::new(move || {
+                let _ = client.cancel_request(&msg_id);
+            }),
+        );
+
+        let inner = Rc::downgrade(&self.inner);
+        self.guard(future.then(move |res| {
+            if let Some(inner) = inner.upgrade() {
+                let _ = inner.borrow_mut().requests.remove(&msg_id);
+            }
+            res
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future;
+
+    // Test that guarded futures complete normally until the token is cancelled.
+    #[test]
+    fn guard() {
+        let token = CancellationToken::new();
+
+        let res = token.guard(future::ok::<_, CoreError>(1)).wait();
+        assert_eq!(unwrap!(res), 1);
+
+        // A pending future fails once the token is cancelled.
+        let pending = token.guard(future::empty::<(), CoreError>());
+        token.clone().cancel();
+        match pending.wait() {
+            Err(CoreError::RequestCancelled) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        // So does anything guarded after cancellation.
+        assert!(token.is_cancelled());
+        match token.guard(future::ok::<_, CoreError>(1)).wait() {
+            Err(CoreError::RequestCancelled) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use routing::{MessageId, XorName};
+use std::collections::VecDeque;
+
+/// Record of a single mutation performed by a client.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuditEntry {
+    /// Name of the operation, e.g. `"PutIData"` or `"MutateMDataEntries"`.
+    pub operation: &'static str,
+    /// Name of the data the mutation targets, or `None` for auth key mutations.
+    pub data_name: Option<XorName>,
+    /// Id of the last message sent for the mutation.
+    pub msg_id: MessageId,
+    /// Outcome of the mutation, with the error description on failure.
+    pub result: Result<(), String>,
+}
+
+/// Ring buffer holding the most recent mutations performed by a client.
+pub struct AuditLog {
+    capacity: usize,
+    entries: VecDeque<AuditEntry>,
+}
+
+impl AuditLog {
+    /// Create an empty log holding at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        AuditLog {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Append an entry, dropping the oldest one if the log is full.
+    pub fn push(&mut self, entry: AuditEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            let _ = self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Return the entries, oldest first.
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.iter().cloned().collect()
+    }
+}
@@ -0,0 +1,36 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use lru_cache::LruCache;
+use routing::{ImmutableData, XorName};
+
+/// Cache of `ImmutableData` which a client consults before fetching data from the network.
+/// Implement this to plug in a different caching strategy, e.g. a disk-backed cache or none at
+/// all. By default clients use an in-memory `LruCache`.
+pub trait DataCache {
+    /// Return the data with the given name, if it's in the cache.
+    fn get(&mut self, name: &XorName) -> Option<ImmutableData>;
+    /// Add the data to the cache under the given name.
+    fn insert(&mut self, name: XorName, data: ImmutableData);
+    /// Remove the data with the given name from the cache.
+    fn remove(&mut self, name: &XorName);
+}
+
+impl DataCache for LruCache<XorName, ImmutableData> {
+    fn get(&mut self, name: &XorName) -> Option<ImmutableData> {
+        self.get_mut(name).cloned()
+    }
+
+    fn insert(&mut self, name: XorName, data: ImmutableData) {
+        let _ = LruCache::insert(self, name, data);
+    }
+
+    fn remove(&mut self, name: &XorName) {
+        let _ = LruCache::remove(self, name);
+    }
+}
@@ -13,15 +13,13 @@ use routing::Client as Routing;
 
 use crate::client::account::{Account as ClientAccount, ClientKeys};
 use crate::client::{
-    setup_routing, spawn_routing_thread, Client, ClientInner, IMMUT_DATA_CACHE_SIZE,
-    REQUEST_TIMEOUT_SECS,
+    setup_routing, spawn_routing_thread, Client, ClientConfig, ClientInner, REQUEST_TIMEOUT_SECS,
 };
 use crate::crypto::{shared_box, shared_secretbox, shared_sign};
 use crate::errors::CoreError;
 use crate::event::NetworkTx;
 use crate::event_loop::CoreMsgTx;
 use crate::utils;
-use lru_cache::LruCache;
 use maidsafe_utilities::serialisation::serialise;
 use routing::XorName;
 use routing::{
@@ -72,8 +70,8 @@ macro_rules! wait_for_response {
 /// Barebones Client object used for testing purposes.
 pub struct CoreClient {
     inner: Rc<RefCell<ClientInner<CoreClient, ()>>>,
-    cm_addr: Authority<XorName>,
-    keys: ClientKeys,
+    cm_addr: Option<Authority<XorName>>,
+    keys: Option<ClientKeys>,
 }
 
 impl CoreClient {
@@ -85,6 +83,27 @@ impl CoreClient {
         el_handle: Handle,
         core_tx: CoreMsgTx<Self, ()>,
         net_tx: NetworkTx,
+    ) -> Result<Self, CoreError> {
+        Self::new_with_config(
+            acc_locator,
+            acc_password,
+            invitation,
+            el_handle,
+            core_tx,
+            net_tx,
+            ClientConfig::default(),
+        )
+    }
+
+    /// Same as `new`, but with the given client configuration instead of the default one.
+    pub fn new_with_config(
+        acc_locator: &str,
+        acc_password: &str,
+        invitation: &str,
+        el_handle: Handle,
+        core_tx: CoreMsgTx<Self, ()>,
+        net_tx: NetworkTx,
+        config: ClientConfig,
     ) -> Result<Self, CoreError> {
         Self::new_impl(
             acc_locator.as_bytes(),
@@ -94,10 +113,41 @@ impl CoreClient {
             core_tx,
             net_tx,
             None,
+            config,
             |routing| routing,
         )
     }
 
+    /// Create an unregistered client, which can only read public data or data it has the
+    /// encryption keys for.
+    pub fn unregistered(
+        el_handle: Handle,
+        core_tx: CoreMsgTx<Self, ()>,
+        net_tx: NetworkTx,
+    ) -> Result<Self, CoreError> {
+        trace!("Creating unregistered client.");
+
+        let (routing, routing_rx) = setup_routing(None, None)?;
+        let joiner = spawn_routing_thread(routing_rx, core_tx.clone(), net_tx.clone());
+
+        let inner = ClientInner::new(
+            el_handle,
+            routing,
+            HashMap::with_capacity(10),
+            ClientConfig::default(),
+            Duration::from_secs(REQUEST_TIMEOUT_SECS),
+            joiner,
+            core_tx,
+            net_tx,
+        );
+
+        Ok(Self {
+            inner: Rc::new(RefCell::new(inner)),
+            cm_addr: None,
+            keys: None,
+        })
+    }
+
     fn new_impl<F>(
         acc_locator: &[u8],
         acc_password: &[u8],
@@ -106,6 +156,7 @@ impl CoreClient {
         core_tx: CoreMsgTx<Self, ()>,
         net_tx: NetworkTx,
         id_seed: Option<&Seed>,
+        config: ClientConfig,
         routing_wrapper_fn: F,
     ) -> Result<Self, CoreError>
     where
@@ -165,19 +216,21 @@ impl CoreClient {
         // Create the client
         let joiner = spawn_routing_thread(routing_rx, core_tx.clone(), net_tx.clone());
 
+        let inner = ClientInner::new(
+            el_handle,
+            routing,
+            HashMap::with_capacity(10),
+            config,
+            Duration::from_secs(REQUEST_TIMEOUT_SECS),
+            joiner,
+            core_tx,
+            net_tx,
+        );
+
         Ok(Self {
-            inner: Rc::new(RefCell::new(ClientInner {
-                el_handle,
-                routing,
-                hooks: HashMap::with_capacity(10),
-                cache: LruCache::new(IMMUT_DATA_CACHE_SIZE),
-                timeout: Duration::from_secs(REQUEST_TIMEOUT_SECS),
-                joiner,
-                net_tx,
-                core_tx,
-            })),
-            cm_addr,
-            keys: maid_keys,
+            inner: Rc::new(RefCell::new(inner)),
+            cm_addr: Some(cm_addr),
+            keys: Some(maid_keys),
         })
     }
 }
@@ -194,7 +247,7 @@ impl Client for CoreClient {
     }
 
     fn cm_addr(&self) -> Option<Authority<XorName>> {
-        Some(self.cm_addr)
+        self.cm_addr
     }
 
     fn inner(&self) -> Rc<RefCell<ClientInner<Self, Self::MsgType>>> {
@@ -202,27 +255,27 @@ impl Client for CoreClient {
     }
 
     fn public_encryption_key(&self) -> Option<box_::PublicKey> {
-        Some(self.keys.enc_pk)
+        self.keys.as_ref().map(|keys| keys.enc_pk)
     }
 
     fn secret_encryption_key(&self) -> Option<shared_box::SecretKey> {
-        Some(self.keys.enc_sk.clone())
+        self.keys.as_ref().map(|keys| keys.enc_sk.clone())
     }
 
     fn public_signing_key(&self) -> Option<sign::PublicKey> {
-        Some(self.keys.sign_pk)
+        self.keys.as_ref().map(|keys| keys.sign_pk)
     }
 
     fn secret_signing_key(&self) -> Option<shared_sign::SecretKey> {
-        Some(self.keys.sign_sk.clone())
+        self.keys.as_ref().map(|keys| keys.sign_sk.clone())
     }
 
     fn secret_symmetric_key(&self) -> Option<shared_secretbox::Key> {
-        Some(self.keys.enc_key.clone())
+        self.keys.as_ref().map(|keys| keys.enc_key.clone())
     }
 
     fn owner_key(&self) -> Option<sign::PublicKey> {
-        Some(self.keys.sign_pk)
+        self.keys.as_ref().map(|keys| keys.sign_pk)
     }
 }
 
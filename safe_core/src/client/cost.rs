@@ -0,0 +1,87 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use self_encryption::{MAX_CHUNK_SIZE, MIN_CHUNK_SIZE};
+
+/// An operation planned against the network, described in enough detail to estimate its cost.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PlannedOp {
+    /// Upload of `size` bytes of content through self-encryption, as done by
+    /// `immutable_data::create` or the NFS `Writer`, followed by a PUT of the resulting data map.
+    UploadContent {
+        /// Size of the content in bytes.
+        size: u64,
+    },
+    /// PUT of a single `ImmutableData`.
+    PutIData,
+    /// PUT of a new `MutableData`.
+    PutMData,
+    /// A single mutation of an existing `MutableData`, i.e. of its entries, permissions or owners.
+    MutateMData,
+}
+
+impl PlannedOp {
+    /// Number of mutations the network charges the account for this operation.
+    pub fn cost(&self) -> u64 {
+        match *self {
+            PlannedOp::UploadContent { size } => num_chunks(size) + 1,
+            PlannedOp::PutIData | PlannedOp::PutMData | PlannedOp::MutateMData => 1,
+        }
+    }
+}
+
+/// Estimate the number of mutations the given operations will cost, to be compared against the
+/// `mutations_available` of the account info.
+pub fn estimate(ops: &[PlannedOp]) -> u64 {
+    ops.iter().map(PlannedOp::cost).sum()
+}
+
+// Number of chunks self-encryption splits content of the given size into. Content too small to be
+// split is kept inline in the data map.
+fn num_chunks(size: u64) -> u64 {
+    let min = u64::from(MIN_CHUNK_SIZE);
+    let max = u64::from(MAX_CHUNK_SIZE);
+
+    if size < 3 * min {
+        0
+    } else if size < 3 * max {
+        3
+    } else {
+        (size + max - 1) / max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test the estimates for content of various sizes and for plain requests.
+    #[test]
+    fn estimate_cost() {
+        let min = u64::from(MIN_CHUNK_SIZE);
+        let max = u64::from(MAX_CHUNK_SIZE);
+
+        assert_eq!(estimate(&[]), 0);
+        assert_eq!(estimate(&[PlannedOp::UploadContent { size: 0 }]), 1);
+        assert_eq!(estimate(&[PlannedOp::UploadContent { size: 3 * min }]), 4);
+        assert_eq!(estimate(&[PlannedOp::UploadContent { size: 3 * max }]), 4);
+        assert_eq!(
+            estimate(&[PlannedOp::UploadContent { size: 3 * max + 1 }]),
+            5
+        );
+        assert_eq!(
+            estimate(&[
+                PlannedOp::PutIData,
+                PlannedOp::PutMData,
+                PlannedOp::MutateMData,
+                PlannedOp::MutateMData,
+            ]),
+            4
+        );
+    }
+}
@@ -12,7 +12,8 @@ use crate::ffi::arrays::{SymNonce, SymSecretKey};
 use crate::ffi::MDataInfo as FfiMDataInfo;
 use crate::ipc::IpcError;
 use crate::utils::{symmetric_decrypt, symmetric_encrypt};
-use ffi_utils::ReprC;
+use ffi_utils::{base64_decode, base64_encode, ReprC};
+use maidsafe_utilities::serialisation::{deserialise, serialise};
 use rand::{OsRng, Rng};
 use routing::{EntryAction, Value, XorName};
 use rust_sodium::crypto::secretbox;
@@ -134,6 +135,26 @@ impl MDataInfo {
         }
     }
 
+    /// Export a token allowing to locate and read the data, e.g. to share a directory read-only.
+    /// It contains the current encryption info (if any), but not a pending new one. Holding the
+    /// token doesn't grant any permission to mutate the data.
+    pub fn export_read_capability(&self) -> Result<String, CoreError> {
+        let info = MDataInfo {
+            name: self.name,
+            type_tag: self.type_tag,
+            enc_info: self.enc_info.clone(),
+            new_enc_info: None,
+        };
+
+        Ok(base64_encode(&serialise(&info)?))
+    }
+
+    /// Open a token created by `export_read_capability`.
+    pub fn open_read_capability(token: &str) -> Result<Self, CoreError> {
+        let encoded = base64_decode(token).map_err(|_| CoreError::ReceivedUnexpectedData)?;
+        Ok(deserialise(&encoded)?)
+    }
+
     /// Construct FFI wrapper for the native Rust object, consuming self.
     pub fn into_repr_c(self) -> FfiMDataInfo {
         let (has_enc_info, enc_key, enc_nonce) = enc_info_into_repr_c(self.enc_info);
@@ -347,6 +368,26 @@ mod tests {
         assert_eq!(unwrap!(info.decrypt(&val)), val);
     }
 
+    // Test that a read capability restores the data's location and current encryption info only.
+    #[test]
+    fn read_capability() {
+        let mut info = unwrap!(MDataInfo::random_private(0));
+        info.start_new_enc_info();
+
+        let token = unwrap!(info.export_read_capability());
+        let opened = unwrap!(MDataInfo::open_read_capability(&token));
+
+        assert_eq!(opened.name, info.name);
+        assert_eq!(opened.type_tag, info.type_tag);
+        assert_eq!(opened.enc_info, info.enc_info);
+        assert_eq!(opened.new_enc_info, None);
+
+        match MDataInfo::open_read_capability("not a token") {
+            Err(CoreError::ReceivedUnexpectedData) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+    }
+
     // Test creating and committing new encryption info.
     #[test]
     fn decrypt() {
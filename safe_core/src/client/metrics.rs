@@ -0,0 +1,66 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::event::CoreEvent;
+use std::time::Duration;
+
+/// Metric emitted by a client on the channels returned by `Client::metrics_stream`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MetricEvent {
+    /// A response to a request has been received.
+    RequestCompleted {
+        /// Kind of the response, e.g. `"GetIData"` or `"Mutation"`.
+        op: &'static str,
+        /// Time between sending the request and receiving the response.
+        latency: Duration,
+        /// Size of the data received, which is only non-zero for `ImmutableData` fetches.
+        bytes: usize,
+        /// Outcome of the request, with the error description on failure.
+        result: Result<(), String>,
+    },
+}
+
+/// Return the metric for the response `event`, or `None` if it doesn't complete a request.
+pub fn request_completed(event: &CoreEvent, latency: Duration) -> Option<MetricEvent> {
+    let (op, bytes, result) = match *event {
+        CoreEvent::GetAccountInfo(ref res) => ("GetAccountInfo", 0, outcome(res)),
+        CoreEvent::Mutation(ref res) => ("Mutation", 0, outcome(res)),
+        CoreEvent::GetIData(ref res) => {
+            let bytes = res.as_ref().map(|data| data.value().len()).unwrap_or(0);
+            ("GetIData", bytes, outcome(res))
+        }
+        CoreEvent::GetMDataVersion(ref res) => ("GetMDataVersion", 0, outcome(res)),
+        CoreEvent::ListMDataEntries(ref res) => ("ListMDataEntries", 0, outcome(res)),
+        CoreEvent::ListMDataKeys(ref res) => ("ListMDataKeys", 0, outcome(res)),
+        CoreEvent::ListMDataValues(ref res) => ("ListMDataValues", 0, outcome(res)),
+        CoreEvent::GetMDataValue(ref res) => ("GetMDataValue", 0, outcome(res)),
+        CoreEvent::ListMDataPermissions(ref res) => ("ListMDataPermissions", 0, outcome(res)),
+        CoreEvent::ListMDataUserPermissions(ref res) => {
+            ("ListMDataUserPermissions", 0, outcome(res))
+        }
+        CoreEvent::ListAuthKeysAndVersion(ref res) => ("ListAuthKeysAndVersion", 0, outcome(res)),
+        CoreEvent::GetMDataShell(ref res) => ("GetMDataShell", 0, outcome(res)),
+        CoreEvent::GetMData(ref res) => ("GetMData", 0, outcome(res)),
+        // The request is retried, so it hasn't completed yet.
+        CoreEvent::RateLimitExceeded => return None,
+        // Cancelled requests fail before their metrics are emitted, and so do requests whose
+        // connection was lost.
+        CoreEvent::RequestCancelled | CoreEvent::ConnectionLost => return None,
+    };
+
+    Some(MetricEvent::RequestCompleted {
+        op,
+        latency,
+        bytes,
+        result,
+    })
+}
+
+fn outcome<T, E: ToString>(res: &Result<T, E>) -> Result<(), String> {
+    res.as_ref().map(|_| ()).map_err(|err| err.to_string())
+}
@@ -34,6 +34,9 @@ pub type RequestHookFn = FnMut(&Request) -> Option<Response> + 'static;
 /// Function that is used to modify responses before they are sent.
 pub type ResponseHookFn = FnMut(Response) -> Response + 'static;
 
+/// Function that is used to delay responses by the returned number of milliseconds.
+pub type ResponseDelayHookFn = FnMut(&Response) -> u64 + 'static;
+
 const CONNECT_THREAD_NAME: &str = "Mock routing connect";
 const DELAY_THREAD_NAME: &str = "Mock routing delay";
 
@@ -88,6 +91,12 @@ pub struct Routing {
     timeout_simulation: bool,
     request_hook: Option<Box<RequestHookFn>>,
     response_hook: Option<Box<ResponseHookFn>>,
+    response_delay_hook: Option<Box<ResponseDelayHookFn>>,
+    request_latency: Option<Duration>,
+    next_mutation_error: Option<ClientError>,
+    next_get_error: Option<ClientError>,
+    storage_quota: Option<u64>,
+    storage_used: Cell<u64>,
 }
 
 impl Routing {
@@ -121,6 +130,12 @@ impl Routing {
             timeout_simulation: false,
             request_hook: None,
             response_hook: None,
+            response_delay_hook: None,
+            request_latency: None,
+            next_mutation_error: None,
+            next_get_error: None,
+            storage_quota: None,
+            storage_used: Cell::new(0),
         })
     }
 
@@ -154,7 +169,13 @@ impl Routing {
 
             let vault = self.lock_vault(false);
             match vault.get_account(&name) {
-                Some(account) => Ok(*account.account_info()),
+                Some(account) => {
+                    let mut account_info = *account.account_info();
+                    if self.storage_quota_reached() {
+                        account_info.mutations_available = 0;
+                    }
+                    Ok(account_info)
+                }
                 None => Err(ClientError::NoSuchAccount),
             }
         };
@@ -177,6 +198,7 @@ impl Routing {
         msg_id: MessageId,
     ) -> Result<(), InterfaceError> {
         let data_name = *data.name();
+        let size = data.value().len() as u64;
         let client_auth = self.client_auth;
         let nae_auth = Authority::NaeManager(data_name);
 
@@ -197,11 +219,14 @@ impl Routing {
                 .and_then(|_| vault.authorise_mutation(&dst, self.client_key()))
                 .and_then(|_| {
                     match vault.get_data(&DataId::immutable(*data.name())) {
-                        // Immutable data is de-duplicated so always allowed
+                        // Immutable data is de-duplicated so always allowed, and takes no
+                        // further storage.
                         Some(Data::Immutable(_)) => Ok(()),
                         Some(_) => Err(ClientError::DataExists),
                         None => {
+                            self.verify_storage_quota(size)?;
                             vault.insert_data(DataId::immutable(data_name), Data::Immutable(data));
+                            self.add_storage_used(size);
                             Ok(())
                         }
                     }
@@ -303,9 +328,12 @@ impl Routing {
                 }
             } else {
                 // Put normal data.
+                let size = data.serialised_size();
+
                 vault
                     .authorise_mutation(&dst, self.client_key())
                     .and_then(|_| Self::verify_owner(&dst, data.owners()))
+                    .and_then(|_| self.verify_storage_quota(size))
                     .and_then(|_| {
                         if vault.contains_data(&data_name) {
                             Err(ClientError::DataExists)
@@ -314,7 +342,10 @@ impl Routing {
                             Ok(())
                         }
                     })
-                    .map(|_| vault.commit_mutation(&dst))
+                    .map(|_| {
+                        self.add_storage_used(size);
+                        vault.commit_mutation(&dst)
+                    })
             }
         };
 
@@ -837,6 +868,17 @@ impl Routing {
             response = hook(response);
         }
 
+        let delay_ms = match self.response_delay_hook {
+            Some(ref mut hook) => delay_ms + hook(&response),
+            None => delay_ms,
+        };
+        let delay_ms = match self.request_latency {
+            Some(latency) => {
+                delay_ms + latency.as_secs() * 1000 + u64::from(latency.subsec_millis())
+            }
+            None => delay_ms,
+        };
+
         let event = Event::Response { response, src, dst };
 
         self.send_event(delay_ms, event)
@@ -1060,6 +1102,22 @@ impl Routing {
         })
     }
 
+    fn verify_storage_quota(&self, size: u64) -> Result<(), ClientError> {
+        match self.storage_quota {
+            Some(quota) if self.storage_used.get() + size > quota => Err(ClientError::LowBalance),
+            _ => Ok(()),
+        }
+    }
+
+    fn storage_quota_reached(&self) -> bool {
+        self.storage_quota
+            .map_or(false, |quota| self.storage_used.get() >= quota)
+    }
+
+    fn add_storage_used(&self, size: u64) {
+        self.storage_used.set(self.storage_used.get() + size);
+    }
+
     fn intercept_request<F>(
         &mut self,
         delay_ms: u64,
@@ -1070,8 +1128,15 @@ impl Routing {
     where
         F: FnOnce() -> Request,
     {
-        let response = if let Some(ref mut hook) = self.request_hook {
-            hook(&request())
+        let response = if self.request_hook.is_some()
+            || self.next_mutation_error.is_some()
+            || self.next_get_error.is_some()
+        {
+            let request = request();
+            self.request_hook
+                .as_mut()
+                .and_then(|hook| hook(&request))
+                .or_else(|| self.take_next_error(&request))
         } else {
             None
         };
@@ -1088,11 +1153,76 @@ impl Routing {
         false
     }
 
+    // Take the error set to fail the next mutation or GET, depending on the request, and turn it
+    // into the response to it.
+    fn take_next_error(&mut self, request: &Request) -> Option<Response> {
+        let error = if is_mutation(request) {
+            self.next_mutation_error.take()
+        } else {
+            self.next_get_error.take()
+        }?;
+
+        error_response(request, error)
+    }
+
     fn client_key(&self) -> &sign::PublicKey {
         self.full_id.public_id().signing_public_key()
     }
 }
 
+fn is_mutation(request: &Request) -> bool {
+    match *request {
+        Request::PutIData { .. }
+        | Request::PutMData { .. }
+        | Request::MutateMDataEntries { .. }
+        | Request::SetMDataUserPermissions { .. }
+        | Request::DelMDataUserPermissions { .. }
+        | Request::ChangeMDataOwner { .. }
+        | Request::InsAuthKey { .. }
+        | Request::DelAuthKey { .. } => true,
+        _ => false,
+    }
+}
+
+// Build the response failing the request with the given error.
+fn error_response(request: &Request, error: ClientError) -> Option<Response> {
+    let res = Err(error);
+
+    let response = match *request {
+        Request::GetAccountInfo(msg_id) => Response::GetAccountInfo { res, msg_id },
+        Request::PutIData { msg_id, .. } => Response::PutIData { res, msg_id },
+        Request::GetIData { msg_id, .. } => Response::GetIData { res, msg_id },
+        Request::PutMData { msg_id, .. } => Response::PutMData { res, msg_id },
+        Request::GetMDataVersion { msg_id, .. } => Response::GetMDataVersion { res, msg_id },
+        Request::GetMData { msg_id, .. } => Response::GetMData { res, msg_id },
+        Request::GetMDataShell { msg_id, .. } => Response::GetMDataShell { res, msg_id },
+        Request::ListMDataEntries { msg_id, .. } => Response::ListMDataEntries { res, msg_id },
+        Request::ListMDataKeys { msg_id, .. } => Response::ListMDataKeys { res, msg_id },
+        Request::ListMDataValues { msg_id, .. } => Response::ListMDataValues { res, msg_id },
+        Request::GetMDataValue { msg_id, .. } => Response::GetMDataValue { res, msg_id },
+        Request::MutateMDataEntries { msg_id, .. } => Response::MutateMDataEntries { res, msg_id },
+        Request::ListMDataPermissions { msg_id, .. } => {
+            Response::ListMDataPermissions { res, msg_id }
+        }
+        Request::ListMDataUserPermissions { msg_id, .. } => {
+            Response::ListMDataUserPermissions { res, msg_id }
+        }
+        Request::SetMDataUserPermissions { msg_id, .. } => {
+            Response::SetMDataUserPermissions { res, msg_id }
+        }
+        Request::DelMDataUserPermissions { msg_id, .. } => {
+            Response::DelMDataUserPermissions { res, msg_id }
+        }
+        Request::ChangeMDataOwner { msg_id, .. } => Response::ChangeMDataOwner { res, msg_id },
+        Request::ListAuthKeysAndVersion(msg_id) => Response::ListAuthKeysAndVersion { res, msg_id },
+        Request::InsAuthKey { msg_id, .. } => Response::InsAuthKey { res, msg_id },
+        Request::DelAuthKey { msg_id, .. } => Response::DelAuthKey { res, msg_id },
+        _ => return None,
+    };
+
+    Some(response)
+}
+
 #[cfg(any(feature = "testing", test))]
 impl Routing {
     /// Set hook function to override response before request is processed, for test purposes.
@@ -1113,6 +1243,34 @@ impl Routing {
         self.response_hook = Some(hook);
     }
 
+    /// Set hook function returning an extra delay, in milliseconds, for each response, for test
+    /// purposes. This allows responses to arrive in a different order than the requests were sent.
+    pub fn set_response_delay_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(&Response) -> u64 + 'static,
+    {
+        let hook: Box<ResponseDelayHookFn> = Box::new(hook);
+        self.response_delay_hook = Some(hook);
+    }
+
+    /// Delay every response by the given latency, on top of any other delay, for test purposes.
+    /// `None` removes the latency.
+    pub fn set_request_latency(&mut self, latency: Option<Duration>) {
+        self.request_latency = latency;
+    }
+
+    /// Fail the next mutation with the given error, regardless of the stored data, for test
+    /// purposes. The error is cleared once used. `None` clears it without using it.
+    pub fn set_next_mutation_error(&mut self, error: Option<ClientError>) {
+        self.next_mutation_error = error;
+    }
+
+    /// Fail the next GET with the given error, regardless of the stored data, for test purposes.
+    /// The error is cleared once used. `None` clears it without using it.
+    pub fn set_next_get_error(&mut self, error: Option<ClientError>) {
+        self.next_get_error = error;
+    }
+
     /// Removes hook function to override response results
     pub fn remove_request_hook(&mut self) {
         self.request_hook = None;
@@ -1123,6 +1281,13 @@ impl Routing {
         self.max_ops_countdown = max_ops_count.map(Cell::new)
     }
 
+    /// Sets a maximum number of bytes that can be PUT through this instance. A PUT that would
+    /// exceed it fails with `LowBalance`, and once it's used up, the account info reports no
+    /// mutations available. `None` removes the quota.
+    pub fn set_storage_quota(&mut self, quota: Option<u64>) {
+        self.storage_quota = quota;
+    }
+
     /// Simulates network disconnect
     pub fn simulate_disconnect(&self) {
         let sender = self.sender.clone();
@@ -1172,6 +1172,48 @@ fn low_balance_check() {
     }
 }
 
+// Test that PUTs fail once the storage quota is used up.
+#[test]
+fn storage_quota() {
+    let (mut routing, routing_rx, full_id) = setup();
+
+    // Create account
+    let owner_key = *full_id.public_id().signing_public_key();
+    let client_mgr = create_account(&mut routing, &routing_rx, owner_key);
+
+    routing.set_storage_quota(Some(300));
+
+    // Fill the quota exactly.
+    let mut stored = Vec::new();
+    for _ in 0..3 {
+        let data = ImmutableData::new(unwrap!(utils::generate_random_vector(100)));
+        let msg_id = MessageId::new();
+        unwrap!(routing.put_idata(client_mgr, data.clone(), msg_id));
+        expect_success!(routing_rx, msg_id, Response::PutIData);
+        stored.push(data);
+    }
+
+    // Re-putting existing data is de-duplicated, so it still succeeds.
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_idata(client_mgr, stored[0].clone(), msg_id));
+    expect_success!(routing_rx, msg_id, Response::PutIData);
+
+    // The next PUT doesn't fit anymore.
+    let data = ImmutableData::new(unwrap!(utils::generate_random_vector(1)));
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_idata(client_mgr, data, msg_id));
+    expect_failure!(
+        routing_rx,
+        msg_id,
+        Response::PutIData,
+        ClientError::LowBalance
+    );
+
+    let acct_info = account_info(&mut routing, &routing_rx, client_mgr);
+    assert_eq!(acct_info.mutations_done, 4);
+    assert_eq!(acct_info.mutations_available, 0);
+}
+
 // Test that using an invalid mock-vault path does not work.
 #[test]
 #[should_panic]
@@ -1254,6 +1296,52 @@ fn config_mock_vault_path() {
     unwrap!(std::fs::remove_dir_all("./tmp"));
 }
 
+// Test that data put through one mock vault is visible to a fresh one using the same file.
+#[test]
+fn mock_vault_persistence() {
+    use std;
+
+    // Don't run this test when the env vars are set.
+    if std::env::var("SAFE_MOCK_IN_MEMORY_STORAGE").is_ok()
+        || std::env::var("SAFE_MOCK_VAULT_PATH").is_ok()
+    {
+        return;
+    }
+
+    let path = std::env::temp_dir().join(format!("mock_vault_{:016x}", rand::random::<u64>()));
+    unwrap!(std::fs::create_dir(&path));
+
+    let config = Config {
+        dev: Some(DevConfig {
+            mock_unlimited_mutations: false,
+            mock_in_memory_storage: false,
+            mock_vault_path: Some(unwrap!(path.to_str()).to_string()),
+        }),
+    };
+
+    let data = ImmutableData::new(unwrap!(utils::generate_random_vector(100)));
+    let nae_mgr = Authority::NaeManager(*data.name());
+
+    {
+        let (mut routing, routing_rx, full_id) = setup_with_config(config.clone());
+        let owner_key = *full_id.public_id().signing_public_key();
+        let client_mgr = create_account(&mut routing, &routing_rx, owner_key);
+
+        let msg_id = MessageId::new();
+        unwrap!(routing.put_idata(client_mgr, data.clone(), msg_id));
+        expect_success!(routing_rx, msg_id, Response::PutIData);
+    }
+
+    let (mut routing, routing_rx, _) = setup_with_config(config);
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.get_idata(nae_mgr, *data.name(), msg_id));
+    let got_data = expect_success!(routing_rx, msg_id, Response::GetIData);
+    assert_eq!(got_data, data);
+
+    unwrap!(std::fs::remove_dir_all(&path));
+}
+
 // Test routing request hooks.
 #[test]
 fn request_hooks() {
@@ -1363,6 +1451,52 @@ fn request_hooks() {
     expect_success!(routing_rx, msg_id, Response::MutateMDataEntries);
 }
 
+// Test failing the next mutation and the next GET with a given error.
+#[test]
+fn next_errors() {
+    let (mut routing, routing_rx, full_id) = setup();
+
+    // Create account
+    let owner_key = *full_id.public_id().signing_public_key();
+    let client_mgr = create_account(&mut routing, &routing_rx, owner_key);
+
+    let data = ImmutableData::new(unwrap!(utils::generate_random_vector(100)));
+    let nae_mgr = Authority::NaeManager(*data.name());
+
+    // The GET error is not used by a mutation, so only the PutIData fails.
+    routing.set_next_mutation_error(Some(ClientError::DataExists));
+    routing.set_next_get_error(Some(ClientError::NoSuchData));
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_idata(client_mgr, data.clone(), msg_id));
+    expect_failure!(
+        routing_rx,
+        msg_id,
+        Response::PutIData,
+        ClientError::DataExists
+    );
+
+    // The error is cleared once used, so the data can be put now.
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_idata(client_mgr, data.clone(), msg_id));
+    expect_success!(routing_rx, msg_id, Response::PutIData);
+
+    // GetIData fails even though the data is stored, then succeeds.
+    let msg_id = MessageId::new();
+    unwrap!(routing.get_idata(nae_mgr, *data.name(), msg_id));
+    expect_failure!(
+        routing_rx,
+        msg_id,
+        Response::GetIData,
+        ClientError::NoSuchData
+    );
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.get_idata(nae_mgr, *data.name(), msg_id));
+    let got_data = expect_success!(routing_rx, msg_id, Response::GetIData);
+    assert_eq!(got_data, data);
+}
+
 // Setup routing with a shared, global vault.
 fn setup() -> (Routing, Receiver<Event>, FullId) {
     let (routing, routing_rx, full_id) = setup_impl();
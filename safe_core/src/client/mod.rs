@@ -8,61 +8,94 @@
 
 /// User Account information.
 pub mod account;
+/// Audit log of mutations.
+pub mod audit;
+/// Pluggable cache of immutable data.
+pub mod cache;
 /// Not exclusively for testing purposes but also for its wait_for_response macro
 #[macro_use]
 pub mod core_client;
+/// Estimation of the cost of planned operations.
+pub mod cost;
 /// `MDataInfo` utilities.
 pub mod mdata_info;
 /// Operations with recovery.
 pub mod recovery;
+/// Backoff policy for retried requests.
+pub mod retry;
+/// Request statistics.
+pub mod stats;
 
+mod metrics;
 #[cfg(feature = "mock-network")]
 mod mock;
+mod request_limiter;
 mod routing_event_loop;
 
 pub use self::account::ClientKeys;
+pub use self::audit::AuditEntry;
+pub use self::cache::DataCache;
+pub use self::cost::PlannedOp;
 pub use self::mdata_info::MDataInfo;
+pub use self::metrics::MetricEvent;
 #[cfg(feature = "mock-network")]
 pub use self::mock::vault::mock_vault_path;
 #[cfg(feature = "mock-network")]
 pub use self::mock::Routing as MockRouting;
+pub use self::retry::RetryPolicy;
+pub use self::stats::Stats;
 
 #[cfg(feature = "mock-network")]
 use self::mock::Routing;
 #[cfg(not(feature = "mock-network"))]
 use routing::Client as Routing;
 
+use self::audit::AuditLog;
+use self::request_limiter::RequestLimiter;
 use crate::crypto::{shared_box, shared_secretbox, shared_sign};
 use crate::errors::CoreError;
-use crate::event::{CoreEvent, NetworkEvent, NetworkTx};
-use crate::event_loop::{CoreFuture, CoreMsgTx};
+use crate::event::{CoreEvent, NetworkEvent, NetworkRx, NetworkTx};
+use crate::event_loop::{CoreFuture, CoreMsgTx, TailFuture};
 use crate::ipc::BootstrapConfig;
-use crate::utils::FutureExt;
+use crate::utils::{self, FutureExt};
 use futures::future::{self, Either, FutureResult, Loop, Then};
-use futures::sync::oneshot;
-use futures::{Complete, Future};
+use futures::sync::{mpsc as futures_mpsc, oneshot};
+use futures::{stream, Complete, Future, Stream};
 use lru_cache::LruCache;
+#[cfg(any(test, feature = "testing"))]
+use maidsafe_utilities::serialisation::{deserialise, serialise};
 use maidsafe_utilities::thread::{self, Joiner};
 use routing::{
-    AccountInfo, Authority, EntryAction, Event, FullId, ImmutableData, InterfaceError, MessageId,
-    MutableData, PermissionSet, User, Value, XorName,
+    AccountInfo, Authority, ClientError, EntryAction, Event, FullId, ImmutableData, InterfaceError,
+    MessageId, MutableData, PermissionSet, User, Value, XorName, XOR_NAME_LEN,
 };
-use rust_sodium::crypto::{box_, sign};
-use std::cell::RefCell;
+use rust_sodium::crypto::{box_, sealedbox, sign};
+use std::cell::{Cell, RefCell};
+use std::cmp;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io;
 use std::rc::Rc;
 use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio_core::reactor::{Handle, Timeout};
 
 /// Capacity of the immutable data cache.
 pub const IMMUT_DATA_CACHE_SIZE: usize = 300;
+/// Capacity of the cache for custom, caller-keyed data.
+pub const CUSTOM_CACHE_SIZE: usize = 100;
 /// Request timeout in seconds.
 pub const REQUEST_TIMEOUT_SECS: u64 = 180;
+/// Default time in seconds a missing immutable data is remembered for.
+pub const NEGATIVE_CACHE_TTL_SECS: u64 = 10;
 
 const CONNECTION_TIMEOUT_SECS: u64 = 40;
 const RETRY_DELAY_MS: u64 = 800;
+// Weight of the existing average when adding a new round trip time sample, as in 1 - 1/N.
+const ROUND_TRIP_SMOOTHING: u32 = 8;
+// Maximum number of concurrent requests issued when checking whether many data exist.
+const MAX_CONCURRENT_EXISTENCE_CHECKS: usize = 8;
+// Age after which the account balance used by the balance check is fetched again.
+const BALANCE_REFRESH_SECS: u64 = 30;
 
 macro_rules! match_event {
     ($r:ident, $event:path) => {
@@ -85,6 +118,38 @@ macro_rules! some_or_err {
     };
 }
 
+/// Configuration of a single client.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ClientConfig {
+    /// Capacity of the immutable data cache, in number of data.
+    pub cache_size: usize,
+    /// How long a `NoSuchData` result for immutable data is remembered, so repeated GETs of the
+    /// same name fail without a network request. `None` disables this.
+    pub negative_cache_ttl: Option<Duration>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            cache_size: IMMUT_DATA_CACHE_SIZE,
+            negative_cache_ttl: Some(Duration::from_secs(NEGATIVE_CACHE_TTL_SECS)),
+        }
+    }
+}
+
+/// State of the connection of a client to the network.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConnectionState {
+    /// Connected to the network.
+    Connected,
+    /// The connection was lost and the client is restarting routing.
+    Reconnecting,
+    /// The connection was lost.
+    Disconnected,
+    /// The client was logged out with `Client::logout` and won't connect again.
+    LoggedOut,
+}
+
 /// Return the `crust::Config` associated with the `crust::Service` (if any).
 pub fn bootstrap_config() -> Result<BootstrapConfig, CoreError> {
     Ok(Routing::bootstrap_config()?)
@@ -139,31 +204,262 @@ pub trait Client: Clone + 'static {
     /// Return the owner signing key.
     fn owner_key(&self) -> Option<sign::PublicKey>;
 
+    /// Return `true` if this client is associated with an account on the network and so is able to
+    /// perform mutations, and `false` for unregistered clients.
+    fn is_registered(&self) -> bool {
+        self.cm_addr().is_some()
+    }
+
+    /// Return the account's network identity, i.e. the hash of the account's public signing key
+    /// which also names its client manager. For apps this is the identity of the owning account.
+    /// Fails with `OperationForbidden` for unregistered clients.
+    fn network_identity(&self) -> Result<XorName, CoreError> {
+        match self.cm_addr() {
+            Some(Authority::ClientManager(name)) => Ok(name),
+            _ => Err(CoreError::OperationForbidden),
+        }
+    }
+
+    /// Encrypt `plain_text` for the owner of `recipient` with the client's encryption keys. The
+    /// result can be decrypted by the recipient calling `decrypt` with this client's public
+    /// encryption key. Fails with `OperationForbidden` for unregistered clients.
+    fn encrypt(
+        &self,
+        plain_text: &[u8],
+        recipient: &box_::PublicKey,
+    ) -> Result<Vec<u8>, CoreError> {
+        let secret_key = self
+            .secret_encryption_key()
+            .ok_or(CoreError::OperationForbidden)?;
+        utils::asymmetric_encrypt(plain_text, recipient, &secret_key)
+    }
+
+    /// Decrypt data encrypted by the owner of `sender` for this client, e.g. by `encrypt`. Fails
+    /// with `AsymmetricDecipherFailure` if the data is corrupt or wasn't meant for this client, and
+    /// with `OperationForbidden` for unregistered clients.
+    fn decrypt(&self, cipher_text: &[u8], sender: &box_::PublicKey) -> Result<Vec<u8>, CoreError> {
+        let secret_key = self
+            .secret_encryption_key()
+            .ok_or(CoreError::OperationForbidden)?;
+        utils::asymmetric_decrypt(cipher_text, sender, &secret_key)
+    }
+
+    /// Encrypt `plain_text` anonymously for the owner of `recipient`, to be decrypted with
+    /// `seal_open`. Unlike `encrypt`, no sender key is involved, so the recipient can't tell who
+    /// sent the data, nor be sure it wasn't altered by someone else who knows their public key.
+    fn seal(plain_text: &[u8], recipient: &box_::PublicKey) -> Vec<u8> {
+        sealedbox::seal(plain_text, recipient)
+    }
+
+    /// Decrypt data encrypted for this client by `seal`. Fails with `AsymmetricDecipherFailure` if
+    /// the data is corrupt or wasn't meant for this client, and with `OperationForbidden` for
+    /// unregistered clients.
+    fn seal_open(&self, cipher_text: &[u8]) -> Result<Vec<u8>, CoreError> {
+        let (public_key, secret_key) = self
+            .encryption_keypair()
+            .ok_or(CoreError::OperationForbidden)?;
+        sealedbox::open(cipher_text, &public_key, &secret_key)
+            .map_err(|()| CoreError::AsymmetricDecipherFailure)
+    }
+
+    /// Return a detached signature of `data` made with the client's secret signing key, to be
+    /// checked with `verify`. Fails with `OperationForbidden` for unregistered clients.
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        let secret_key = self
+            .secret_signing_key()
+            .ok_or(CoreError::OperationForbidden)?;
+        Ok(sign::sign_detached(data, &secret_key).0.to_vec())
+    }
+
+    /// Return `true` if `signature` is a valid detached signature of `data` by the owner of
+    /// `public_key`, e.g. one made by `sign`.
+    fn verify(data: &[u8], signature: &[u8], public_key: &sign::PublicKey) -> bool {
+        match sign::Signature::from_slice(signature) {
+            Some(signature) => sign::verify_detached(&signature, data, public_key),
+            None => false,
+        }
+    }
+
     /// Set request timeout.
     fn set_timeout(&self, duration: Duration) {
         let inner = self.inner();
         inner.borrow_mut().timeout = duration;
     }
 
-    /// Restart the routing client and reconnect to the network.
+    /// Return the state of the connection to the network.
+    fn connection_state(&self) -> ConnectionState {
+        let inner = self.inner();
+        let state = inner.borrow().connection_state;
+        state
+    }
+
+    /// Try to restart routing up to `max_attempts` times when the connection to the network is lost,
+    /// waiting between the attempts as the retry policy says. `0`, the default, disables this,
+    /// leaving it to the caller to call `restart_routing` on `NetworkEvent::Disconnected`.
+    fn set_auto_reconnect(&self, max_attempts: u8) {
+        let inner = self.inner();
+        inner.borrow_mut().reconnect_attempts = max_attempts;
+    }
+
+    /// Set the retry policy used by the operations with recovery.
+    fn set_default_retry_policy(&self, policy: RetryPolicy) {
+        let inner = self.inner();
+        inner.borrow_mut().retry_policy = policy;
+    }
+
+    /// Return the retry policy used by the operations with recovery.
+    fn retry_policy(&self) -> RetryPolicy {
+        let inner = self.inner();
+        let policy = inner.borrow().retry_policy;
+        policy
+    }
+
+    /// Enable or disable checking the account balance before sending mutations. When enabled,
+    /// mutations the account can't afford fail locally with `InsufficientBalance` instead of being
+    /// sent. The balance is fetched from the network at most every `BALANCE_REFRESH_SECS` seconds
+    /// and tracked locally in between.
+    fn set_balance_check(&self, enabled: bool) {
+        let inner = self.inner();
+        let mut inner = inner.borrow_mut();
+        inner.balance_check = enabled;
+        inner.balance = None;
+    }
+
+    /// Limit the number of requests in flight at once to `max`, or remove the limit with `None`.
+    /// Requests made while the limit is reached are only sent once earlier ones complete.
+    fn set_max_in_flight(&self, max: Option<usize>) {
+        let limiter = self.inner().borrow().limiter.clone();
+        limiter.set_max(max);
+    }
+
+    /// Return a new stream of metrics about this client, such as the latency and outcome of every
+    /// completed request. Metrics are emitted from the event loop thread and can be consumed from
+    /// any thread. Dropping the receiver closes the stream.
+    fn metrics_stream(&self) -> Receiver<MetricEvent> {
+        let (tx, rx) = mpsc::channel();
+        let inner = self.inner();
+        inner.borrow_mut().metrics_txs.push(tx);
+        rx
+    }
+
+    /// Subscribe to the network events of this client, i.e. losing and regaining the connection.
+    /// Events are sent from the event loop thread in the order they happen, and only while the
+    /// receiver is alive. Disconnections of routing already replaced by `restart_routing` aren't
+    /// reported.
+    fn subscribe_network_events(&self) -> NetworkRx {
+        let (tx, rx) = futures_mpsc::unbounded();
+        let inner = self.inner();
+        inner.borrow_mut().network_txs.push(tx);
+        rx
+    }
+
+    /// Return the rolling average of the round trip times of recent requests, or `None` if no
+    /// response has been received yet.
+    fn avg_round_trip(&self) -> Option<Duration> {
+        let inner = self.inner();
+        let avg_round_trip = inner.borrow().avg_round_trip;
+        avg_round_trip
+    }
+
+    /// Return the counts of the requests this client has issued to the network so far.
+    fn stats(&self) -> Stats {
+        let inner = self.inner();
+        let stats = inner.borrow().stats;
+        stats
+    }
+
+    /// Return the number of immutable data GETs served from the cache so far.
+    fn cache_hits(&self) -> u64 {
+        self.stats().cache_hits
+    }
+
+    /// Return the number of immutable data GETs that missed the cache and went to the network.
+    fn cache_misses(&self) -> u64 {
+        self.stats().cache_misses
+    }
+
+    /// Start recording the most recent `capacity` mutations performed by this client, discarding
+    /// any entries recorded so far.
+    fn enable_audit_log(&self, capacity: usize) {
+        let inner = self.inner();
+        inner.borrow_mut().audit_log = Some(AuditLog::new(capacity));
+    }
+
+    /// Return the recorded mutations, oldest first. Empty if the audit log isn't enabled.
+    fn audit_log(&self) -> Vec<AuditEntry> {
+        let inner = self.inner();
+        let entries = inner
+            .borrow()
+            .audit_log
+            .as_ref()
+            .map_or_else(Vec::new, AuditLog::entries);
+        entries
+    }
+
+    /// Restart the routing client and reconnect to the network. Fails with `OperationAborted` once
+    /// the client is logged out.
     fn restart_routing(&self) -> Result<(), CoreError> {
         let opt_id = self.full_id();
         let inner = self.inner();
         let mut inner = inner.borrow_mut();
 
+        if inner.connection_state == ConnectionState::LoggedOut {
+            return Err(CoreError::OperationAborted);
+        }
+
         let (routing, routing_rx) = setup_routing(opt_id, self.config())?;
 
+        // Disconnections of the routing being replaced are no longer of interest.
+        inner.routing_since = Some(Instant::now());
         let joiner = spawn_routing_thread(routing_rx, inner.core_tx.clone(), inner.net_tx.clone());
 
         inner.hooks.clear();
-        inner.routing = routing;
-        inner.joiner = joiner;
+        inner.routing = Some(routing);
+        inner.joiner = Some(joiner);
+        inner.connection_state = ConnectionState::Connected;
+        inner.emit_network_event(NetworkEvent::Connected);
 
         inner.net_tx.unbounded_send(NetworkEvent::Connected)?;
 
         Ok(())
     }
 
+    /// Disconnect from the network and wait for the routing event loop thread to finish. Requests
+    /// still waiting for a response fail with `OperationAborted`. Clones of this client share its
+    /// connection, so they are logged out too: their state becomes `ConnectionState::LoggedOut`
+    /// and their requests fail with `OperationAborted` from then on.
+    fn logout(self) {
+        let inner = self.inner();
+        let (routing, joiner) = {
+            let mut inner = inner.borrow_mut();
+
+            // The disconnection of the routing being dropped is expected, so don't reconnect.
+            inner.routing_since = Some(Instant::now());
+            inner.connection_state = ConnectionState::LoggedOut;
+            inner.hooks.clear();
+            (inner.routing.take(), inner.joiner.take())
+        };
+
+        // Routing disconnects once dropped, which in turn stops its event loop thread.
+        drop(routing);
+        drop(joiner);
+    }
+
+    /// Cancel the request sent under the given message id, e.g. by `get_idata_with_id`. Its future
+    /// fails with `CoreError::RequestCancelled` straight away, and a late response is ignored.
+    /// Returns `false` if no such request is waiting for a response, including one still queued
+    /// because of the limit set by `set_max_in_flight`.
+    fn cancel_request(&self, id: &MessageId) -> bool {
+        let inner = self.inner();
+        let hook = inner.borrow_mut().hooks.remove(id);
+        if let Some(hook) = hook {
+            let _ = hook.send(CoreEvent::RequestCancelled);
+            true
+        } else {
+            false
+        }
+    }
+
     #[doc(hidden)]
     fn fire_hook(&self, id: &MessageId, event: CoreEvent) {
         // Using in `if` keeps borrow alive. Do not try to combine the 2 lines into one.
@@ -177,39 +473,153 @@ pub trait Client: Clone + 'static {
     /// Get immutable data from the network. If the data exists locally in the cache then it will be
     /// immediately returned without making an actual network request.
     fn get_idata(&self, name: XorName) -> Box<CoreFuture<ImmutableData>> {
-        trace!("GetIData for {:?}", name);
+        fetch_idata(self, name, None)
+    }
 
+    /// Same as `get_idata`, but also return the message id the request is sent under, which can be
+    /// passed to `cancel_request`. No request is sent for data found in the cache.
+    fn get_idata_with_id(&self, name: XorName) -> (MessageId, Box<CoreFuture<ImmutableData>>) {
+        let msg_id = new_message_id();
+        (msg_id, fetch_idata(self, name, Some(msg_id)))
+    }
+
+    /// Get `len` bytes of the raw value of immutable data, starting at `offset`. The range is
+    /// clamped to the end of the value.
+    ///
+    /// Routing has no ranged GET, so the whole chunk is fetched (or taken from the cache) and then
+    /// sliced. This costs no more than a full `get_idata`, as chunks are bounded in size.
+    fn get_idata_range(
+        &self,
+        name: XorName,
+        offset: usize,
+        len: usize,
+    ) -> Box<CoreFuture<Vec<u8>>> {
+        trace!("GetIData range {}+{} for {:?}", offset, len, name);
+
+        self.get_idata(name)
+            .map(move |data| {
+                let value = data.value();
+                let start = cmp::min(offset, value.len());
+                let end = cmp::min(start.saturating_add(len), value.len());
+                value[start..end].to_vec()
+            })
+            .into_box()
+    }
+
+    /// Check which of several `ImmutableData` exist on the network. The result is in the same
+    /// order as `names`. At most `MAX_CONCURRENT_EXISTENCE_CHECKS` requests are in flight at once,
+    /// and data already in the cache is reported as existing without a network request.
+    fn idata_exists_many(&self, names: Vec<XorName>) -> Box<CoreFuture<Vec<(XorName, bool)>>> {
+        trace!("Checking existence of {} ImmutableData", names.len());
+
+        let client = self.clone();
+
+        stream::iter_ok(names)
+            .map(move |name| {
+                client.get_idata(name).then(move |res| match res {
+                    Ok(_) => Ok((name, true)),
+                    Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => {
+                        Ok((name, false))
+                    }
+                    Err(error) => Err(error),
+                })
+            })
+            .buffered(MAX_CONCURRENT_EXISTENCE_CHECKS)
+            .collect()
+            .into_box()
+    }
+
+    /// Get several `ImmutableData` concurrently. The result is in the same order as `names`. Fails
+    /// if any of the requests fails.
+    fn get_idata_many(&self, names: Vec<XorName>) -> Box<CoreFuture<Vec<ImmutableData>>> {
+        trace!("GetIData for {} ImmutableData", names.len());
+
+        let futures: Vec<_> = names.into_iter().map(|name| self.get_idata(name)).collect();
+        future::join_all(futures).into_box()
+    }
+
+    /// Get several `ImmutableData` concurrently, reporting the result of each request separately.
+    /// The result is in the same order as `names`.
+    fn get_idata_many_partial(
+        &self,
+        names: Vec<XorName>,
+    ) -> Box<CoreFuture<Vec<Result<ImmutableData, CoreError>>>> {
+        trace!("GetIData for {} ImmutableData", names.len());
+
+        let futures: Vec<_> = names
+            .into_iter()
+            .map(|name| self.get_idata(name).then(Ok::<_, CoreError>))
+            .collect();
+        future::join_all(futures).into_box()
+    }
+
+    /// Replace the cache of immutable data used by this client, discarding the current cache.
+    fn set_data_cache(&self, cache: Box<DataCache>) {
         let inner = self.inner();
-        if let Some(data) = inner.borrow_mut().cache.get_mut(&name) {
-            trace!("ImmutableData found in cache.");
-            return future::ok(data.clone()).into_box();
-        }
+        inner.borrow_mut().cache = cache;
+    }
 
-        let inner = Rc::downgrade(&self.inner());
-        send(self, move |routing, msg_id| {
-            routing.get_idata(Authority::NaeManager(name), name, msg_id)
-        })
-        .and_then(|event| match_event!(event, CoreEvent::GetIData))
-        .map(move |data| {
-            if let Some(inner) = inner.upgrade() {
-                // Put to cache
-                let _ = inner.borrow_mut().cache.insert(*data.name(), data.clone());
-            }
-            data
-        })
-        .into_box()
+    /// Store `data` in the client's cache for custom data under `key`, replacing any previous
+    /// value. This cache is separate from the immutable data one, so it can hold derived forms of
+    /// data (e.g. decrypted or decompressed content) without clashing with the raw chunks.
+    fn cache_put_custom(&self, key: String, data: Vec<u8>) {
+        let inner = self.inner();
+        let _ = inner.borrow_mut().custom_cache.insert(key, data);
+    }
+
+    /// Get the data stored under `key` in the client's cache for custom data, if any.
+    fn cache_get_custom(&self, key: &str) -> Option<Vec<u8>> {
+        let inner = self.inner();
+        let data = inner.borrow_mut().custom_cache.get_mut(key).cloned();
+        data
     }
 
     // TODO All these return the same future from all branches. So convert to impl
     // Trait when it arrives in stable. Change from `Box<CoreFuture>` -> `impl
     // CoreFuture`.
-    /// Put immutable data onto the network.
+    /// Put immutable data onto the network. Data exceeding the maximum chunk size is rejected
+    /// locally with `DataTooLarge`, without making a network request. Once stored, the data is
+    /// added to the cache.
     fn put_idata(&self, data: ImmutableData) -> Box<CoreFuture<()>> {
         trace!("PutIData for {:?}", data);
 
-        send_mutation(self, move |routing, dst, msg_id| {
+        if !data.validate_size() {
+            return err!(ClientError::DataTooLarge);
+        }
+
+        let inner = Rc::downgrade(&self.inner());
+        let name = *data.name();
+        let data2 = data.clone();
+
+        count_request(self, |stats| stats.idata_puts += 1);
+        send_mutation(self, "PutIData", Some(name), move |routing, dst, msg_id| {
             routing.put_idata(dst, data.clone(), msg_id)
         })
+        .map(move |()| {
+            // Immutable data is content-addressed, so what was just stored can be served from
+            // the cache straight away.
+            if let Some(inner) = inner.upgrade() {
+                let mut inner = inner.borrow_mut();
+                let _ = inner.negative_cache.remove(&name);
+                inner.cache.insert(name, data2);
+            }
+        })
+        .into_box()
+    }
+
+    /// Put several `ImmutableData` concurrently, reporting the result of each request separately.
+    /// The result is in the same order as `data`.
+    fn put_idata_many(
+        &self,
+        data: Vec<ImmutableData>,
+    ) -> Box<CoreFuture<Vec<Result<(), CoreError>>>> {
+        trace!("PutIData for {} ImmutableData", data.len());
+
+        let futures: Vec<_> = data
+            .into_iter()
+            .map(|data| self.put_idata(data).then(Ok::<_, CoreError>))
+            .collect();
+        future::join_all(futures).into_box()
     }
 
     /// Put `MutableData` onto the network.
@@ -217,9 +627,28 @@ pub trait Client: Clone + 'static {
         trace!("PutMData for {:?}", data);
 
         let requester = some_or_err!(self.public_signing_key());
-        send_mutation(self, move |routing, dst, msg_id| {
-            routing.put_mdata(dst, data.clone(), msg_id, requester)
-        })
+        count_request(self, |stats| stats.mdata_mutations += 1);
+        send_mutation(
+            self,
+            "PutMData",
+            Some(*data.name()),
+            move |routing, dst, msg_id| routing.put_mdata(dst, data.clone(), msg_id, requester),
+        )
+    }
+
+    /// Put several `MutableData` concurrently, reporting the result of each request separately.
+    /// The result is in the same order as `data`.
+    fn put_mdata_many(
+        &self,
+        data: Vec<MutableData>,
+    ) -> Box<CoreFuture<Vec<Result<(), CoreError>>>> {
+        trace!("PutMData for {} MutableData", data.len());
+
+        let futures: Vec<_> = data
+            .into_iter()
+            .map(|data| self.put_mdata(data).then(Ok::<_, CoreError>))
+            .collect();
+        future::join_all(futures).into_box()
     }
 
     /// Mutates `MutableData` entries in bulk.
@@ -232,15 +661,22 @@ pub trait Client: Clone + 'static {
         trace!("PutMData for {:?}", name);
 
         let requester = some_or_err!(self.public_signing_key());
-        send_mutation(self, move |routing, dst, msg_id| {
-            routing.mutate_mdata_entries(dst, name, tag, actions.clone(), msg_id, requester)
-        })
+        count_request(self, |stats| stats.mdata_mutations += 1);
+        send_mutation(
+            self,
+            "MutateMDataEntries",
+            Some(name),
+            move |routing, dst, msg_id| {
+                routing.mutate_mdata_entries(dst, name, tag, actions.clone(), msg_id, requester)
+            },
+        )
     }
 
     /// Get entire `MutableData` from the network.
     fn get_mdata(&self, name: XorName, tag: u64) -> Box<CoreFuture<MutableData>> {
         trace!("GetMData for {:?}", name);
 
+        count_request(self, |stats| stats.mdata_gets += 1);
         send(self, move |routing, msg_id| {
             routing.get_mdata(Authority::NaeManager(name), name, tag, msg_id)
         })
@@ -252,6 +688,7 @@ pub trait Client: Clone + 'static {
     fn get_mdata_shell(&self, name: XorName, tag: u64) -> Box<CoreFuture<MutableData>> {
         trace!("GetMDataShell for {:?}", name);
 
+        count_request(self, |stats| stats.mdata_gets += 1);
         send(self, move |routing, msg_id| {
             routing.get_mdata_shell(Authority::NaeManager(name), name, tag, msg_id)
         })
@@ -263,6 +700,7 @@ pub trait Client: Clone + 'static {
     fn get_mdata_version(&self, name: XorName, tag: u64) -> Box<CoreFuture<u64>> {
         trace!("GetMDataVersion for {:?}", name);
 
+        count_request(self, |stats| stats.mdata_gets += 1);
         send(self, move |routing, msg_id| {
             routing.get_mdata_version(Authority::NaeManager(name), name, tag, msg_id)
         })
@@ -270,6 +708,25 @@ pub trait Client: Clone + 'static {
         .into_box()
     }
 
+    /// Get the current versions of several `MutableData` concurrently. The result is in the same
+    /// order as `ids`, with `None` for data which doesn't exist on the network.
+    fn get_mdata_versions(&self, ids: Vec<(XorName, u64)>) -> Box<CoreFuture<Vec<Option<u64>>>> {
+        trace!("GetMDataVersion for {} MutableData", ids.len());
+
+        let futures: Vec<_> = ids
+            .into_iter()
+            .map(|(name, tag)| {
+                self.get_mdata_version(name, tag).then(|res| match res {
+                    Ok(version) => Ok(Some(version)),
+                    Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => Ok(None),
+                    Err(error) => Err(error),
+                })
+            })
+            .collect();
+
+        future::join_all(futures).into_box()
+    }
+
     /// Return a complete list of entries in `MutableData`.
     fn list_mdata_entries(
         &self,
@@ -278,6 +735,7 @@ pub trait Client: Clone + 'static {
     ) -> Box<CoreFuture<BTreeMap<Vec<u8>, Value>>> {
         trace!("ListMDataEntries for {:?}", name);
 
+        count_request(self, |stats| stats.mdata_gets += 1);
         send(self, move |routing, msg_id| {
             routing.list_mdata_entries(Authority::NaeManager(name), name, tag, msg_id)
         })
@@ -289,6 +747,7 @@ pub trait Client: Clone + 'static {
     fn list_mdata_keys(&self, name: XorName, tag: u64) -> Box<CoreFuture<BTreeSet<Vec<u8>>>> {
         trace!("ListMDataKeys for {:?}", name);
 
+        count_request(self, |stats| stats.mdata_gets += 1);
         send(self, move |routing, msg_id| {
             routing.list_mdata_keys(Authority::NaeManager(name), name, tag, msg_id)
         })
@@ -300,6 +759,7 @@ pub trait Client: Clone + 'static {
     fn list_mdata_values(&self, name: XorName, tag: u64) -> Box<CoreFuture<Vec<Value>>> {
         trace!("ListMDataValues for {:?}", name);
 
+        count_request(self, |stats| stats.mdata_gets += 1);
         send(self, move |routing, msg_id| {
             routing.list_mdata_values(Authority::NaeManager(name), name, tag, msg_id)
         })
@@ -311,6 +771,7 @@ pub trait Client: Clone + 'static {
     fn get_mdata_value(&self, name: XorName, tag: u64, key: Vec<u8>) -> Box<CoreFuture<Value>> {
         trace!("GetMDataValue for {:?}", name);
 
+        count_request(self, |stats| stats.mdata_gets += 1);
         send(self, move |routing, msg_id| {
             routing.get_mdata_value(Authority::NaeManager(name), name, tag, key.clone(), msg_id)
         })
@@ -318,11 +779,26 @@ pub trait Client: Clone + 'static {
         .into_box()
     }
 
+    /// Check whether a `MutableData` entry has been deleted. Entries can't be removed from
+    /// `MutableData`, so deleting one leaves an entry with empty content behind. Fails with
+    /// `NoSuchEntry` if the entry never existed.
+    fn is_mdata_entry_deleted(
+        &self,
+        name: XorName,
+        tag: u64,
+        key: Vec<u8>,
+    ) -> Box<CoreFuture<bool>> {
+        self.get_mdata_value(name, tag, key)
+            .map(|value| value.content.is_empty())
+            .into_box()
+    }
+
     /// Get data from the network.
     fn get_account_info(&self) -> Box<CoreFuture<AccountInfo>> {
         trace!("Account info GET issued.");
 
         let dst = some_or_err!(self.cm_addr());
+        count_request(self, |stats| stats.account_requests += 1);
         send(self, move |routing, msg_id| {
             routing.get_account_info(dst, msg_id)
         })
@@ -330,6 +806,56 @@ pub trait Client: Clone + 'static {
         .into_box()
     }
 
+    /// Return the percentage (from 0 to 100) of the account's mutation allowance used so far, e.g.
+    /// for displaying a usage bar. An account without any allowance reports 0.
+    fn storage_usage_percent(&self) -> Box<CoreFuture<f64>> {
+        self.get_account_info()
+            .map(|info| {
+                let total = info.mutations_done + info.mutations_available;
+                if total == 0 {
+                    0.0
+                } else {
+                    info.mutations_done as f64 / total as f64 * 100.0
+                }
+            })
+            .into_box()
+    }
+
+    /// Estimate the number of mutations the given operations will cost the account. This is a
+    /// local computation which can be compared against the `mutations_available` reported by
+    /// `get_account_info` before starting a large upload.
+    fn estimate_cost(&self, ops: &[PlannedOp]) -> u64 {
+        cost::estimate(ops)
+    }
+
+    /// Check that the network is reachable by round-tripping a cheap request, and return the
+    /// measured latency. Registered clients fetch their account info, unregistered ones look up a
+    /// sentinel `ImmutableData` for which `NoSuchData` counts as a successful response. Fails if
+    /// the request fails or times out.
+    fn health_check(&self) -> Box<CoreFuture<Duration>> {
+        trace!("Health check");
+
+        let started_at = Instant::now();
+
+        let fut = if self.is_registered() {
+            self.get_account_info().map(|_| ()).into_box()
+        } else {
+            // Bypass the caches, which could answer without reaching the network.
+            let name = XorName([0; XOR_NAME_LEN]);
+            send(self, move |routing, msg_id| {
+                routing.get_idata(Authority::NaeManager(name), name, msg_id)
+            })
+            .and_then(|event| match_event!(event, CoreEvent::GetIData))
+            .then(|res| match res {
+                Ok(_) | Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => Ok(()),
+                Err(error) => Err(error),
+            })
+            .into_box()
+        };
+
+        fut.map(move |()| started_at.elapsed()).into_box()
+    }
+
     /// Return a list of permissions in `MutableData` stored on the network.
     fn list_mdata_permissions(
         &self,
@@ -338,6 +864,7 @@ pub trait Client: Clone + 'static {
     ) -> Box<CoreFuture<BTreeMap<User, PermissionSet>>> {
         trace!("ListMDataPermissions for {:?}", name);
 
+        count_request(self, |stats| stats.mdata_gets += 1);
         send(self, move |routing, msg_id| {
             routing.list_mdata_permissions(Authority::NaeManager(name), name, tag, msg_id)
         })
@@ -354,6 +881,7 @@ pub trait Client: Clone + 'static {
     ) -> Box<CoreFuture<PermissionSet>> {
         trace!("ListMDataUserPermissions for {:?}", name);
 
+        count_request(self, |stats| stats.mdata_gets += 1);
         send(self, move |routing, msg_id| {
             let dst = Authority::NaeManager(name);
             routing.list_mdata_user_permissions(dst, name, tag, user, msg_id)
@@ -374,18 +902,24 @@ pub trait Client: Clone + 'static {
         trace!("SetMDataUserPermissions for {:?}", name);
 
         let requester = some_or_err!(self.public_signing_key());
-        send_mutation(self, move |routing, dst, msg_id| {
-            routing.set_mdata_user_permissions(
-                dst,
-                name,
-                tag,
-                user,
-                permissions,
-                version,
-                msg_id,
-                requester,
-            )
-        })
+        count_request(self, |stats| stats.mdata_mutations += 1);
+        send_mutation(
+            self,
+            "SetMDataUserPermissions",
+            Some(name),
+            move |routing, dst, msg_id| {
+                routing.set_mdata_user_permissions(
+                    dst,
+                    name,
+                    tag,
+                    user,
+                    permissions,
+                    version,
+                    msg_id,
+                    requester,
+                )
+            },
+        )
     }
 
     /// Deletes a permission set for a given user
@@ -399,9 +933,15 @@ pub trait Client: Clone + 'static {
         trace!("DelMDataUserPermissions for {:?}", name);
 
         let requester = some_or_err!(self.public_signing_key());
-        send_mutation(self, move |routing, dst, msg_id| {
-            routing.del_mdata_user_permissions(dst, name, tag, user, version, msg_id, requester)
-        })
+        count_request(self, |stats| stats.mdata_mutations += 1);
+        send_mutation(
+            self,
+            "DelMDataUserPermissions",
+            Some(name),
+            move |routing, dst, msg_id| {
+                routing.del_mdata_user_permissions(dst, name, tag, user, version, msg_id, requester)
+            },
+        )
     }
 
     /// Sends an ownership transfer request.
@@ -414,9 +954,15 @@ pub trait Client: Clone + 'static {
     ) -> Box<CoreFuture<()>> {
         trace!("ChangeMDataOwner for {:?}", name);
 
-        send_mutation(self, move |routing, dst, msg_id| {
-            routing.change_mdata_owner(dst, name, tag, btree_set![new_owner], version, msg_id)
-        })
+        count_request(self, |stats| stats.mdata_mutations += 1);
+        send_mutation(
+            self,
+            "ChangeMDataOwner",
+            Some(name),
+            move |routing, dst, msg_id| {
+                routing.change_mdata_owner(dst, name, tag, btree_set![new_owner], version, msg_id)
+            },
+        )
     }
 
     /// Fetches a list of authorised keys and version in MaidManager.
@@ -424,6 +970,7 @@ pub trait Client: Clone + 'static {
         trace!("ListAuthKeysAndVersion");
 
         let dst = some_or_err!(self.cm_addr());
+        count_request(self, |stats| stats.account_requests += 1);
         send(self, move |routing, msg_id| {
             routing.list_auth_keys_and_version(dst, msg_id)
         })
@@ -435,7 +982,8 @@ pub trait Client: Clone + 'static {
     fn ins_auth_key(&self, key: sign::PublicKey, version: u64) -> Box<CoreFuture<()>> {
         trace!("InsAuthKey ({:?})", key);
 
-        send_mutation(self, move |routing, dst, msg_id| {
+        count_request(self, |stats| stats.account_requests += 1);
+        send_mutation(self, "InsAuthKey", None, move |routing, dst, msg_id| {
             routing.ins_auth_key(dst, key, version, msg_id)
         })
     }
@@ -444,11 +992,35 @@ pub trait Client: Clone + 'static {
     fn del_auth_key(&self, key: sign::PublicKey, version: u64) -> Box<CoreFuture<()>> {
         trace!("DelAuthKey ({:?})", key);
 
-        send_mutation(self, move |routing, dst, msg_id| {
+        count_request(self, |stats| stats.account_requests += 1);
+        send_mutation(self, "DelAuthKey", None, move |routing, dst, msg_id| {
             routing.del_auth_key(dst, key, version, msg_id)
         })
     }
 
+    #[cfg(any(test, feature = "testing"))]
+    #[doc(hidden)]
+    fn pending_requests(&self) -> usize {
+        let inner = self.inner();
+        let count = inner.borrow().hooks.len();
+        count
+    }
+
+    #[cfg(any(
+        all(test, feature = "mock-network"),
+        all(feature = "testing", feature = "mock-network")
+    ))]
+    #[doc(hidden)]
+    fn with_routing<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Routing) -> R,
+    {
+        let inner = self.inner();
+        let mut inner = inner.borrow_mut();
+        let routing = unwrap!(inner.routing_mut());
+        f(routing)
+    }
+
     #[cfg(any(
         all(test, feature = "mock-network"),
         all(feature = "testing", feature = "mock-network")
@@ -456,7 +1028,7 @@ pub trait Client: Clone + 'static {
     #[doc(hidden)]
     fn set_network_limits(&self, max_ops_count: Option<u64>) {
         let inner = self.inner();
-        inner.borrow_mut().routing.set_network_limits(max_ops_count);
+        unwrap!(inner.borrow_mut().routing_mut()).set_network_limits(max_ops_count);
     }
 
     #[cfg(any(
@@ -466,7 +1038,7 @@ pub trait Client: Clone + 'static {
     #[doc(hidden)]
     fn simulate_network_disconnect(&self) {
         let inner = self.inner();
-        inner.borrow_mut().routing.simulate_disconnect();
+        unwrap!(inner.borrow_mut().routing_mut()).simulate_disconnect();
     }
 
     #[cfg(any(
@@ -476,7 +1048,7 @@ pub trait Client: Clone + 'static {
     #[doc(hidden)]
     fn set_simulate_timeout(&self, enabled: bool) {
         let inner = self.inner();
-        inner.borrow_mut().routing.set_simulate_timeout(enabled);
+        unwrap!(inner.borrow_mut().routing_mut()).set_simulate_timeout(enabled);
     }
 }
 
@@ -486,13 +1058,32 @@ pub trait Client: Clone + 'static {
 /// composed around this struct.
 pub struct ClientInner<C: Client, T> {
     el_handle: Handle,
-    routing: Routing,
+    // `None` after logging out.
+    routing: Option<Routing>,
     hooks: HashMap<MessageId, Complete<CoreEvent>>,
-    cache: LruCache<XorName, ImmutableData>,
+    cache: Box<DataCache>,
+    custom_cache: LruCache<String, Vec<u8>>,
     timeout: Duration,
-    joiner: Joiner,
+    joiner: Option<Joiner>,
     core_tx: CoreMsgTx<C, T>,
     net_tx: NetworkTx,
+    avg_round_trip: Option<Duration>,
+    stats: Stats,
+    audit_log: Option<AuditLog>,
+    limiter: RequestLimiter,
+    metrics_txs: Vec<mpsc::Sender<MetricEvent>>,
+    network_txs: Vec<NetworkTx>,
+    retry_policy: RetryPolicy,
+    balance_check: bool,
+    // Mutations available to the account and when they were last fetched from the network.
+    balance: Option<(u64, Instant)>,
+    // Immutable data recently found missing on the network, and when.
+    negative_cache: HashMap<XorName, Instant>,
+    negative_cache_ttl: Option<Duration>,
+    reconnect_attempts: u8,
+    // When routing was last restarted, if ever.
+    routing_since: Option<Instant>,
+    connection_state: ConnectionState,
 }
 
 impl<C: Client, T> ClientInner<C, T> {
@@ -501,7 +1092,7 @@ impl<C: Client, T> ClientInner<C, T> {
         el_handle: Handle,
         routing: Routing,
         hooks: HashMap<MessageId, Complete<CoreEvent>>,
-        cache: LruCache<XorName, ImmutableData>,
+        config: ClientConfig,
         timeout: Duration,
         joiner: Joiner,
         core_tx: CoreMsgTx<C, T>,
@@ -509,15 +1100,86 @@ impl<C: Client, T> ClientInner<C, T> {
     ) -> ClientInner<C, T> {
         ClientInner {
             el_handle,
-            routing,
+            routing: Some(routing),
             hooks,
-            cache,
+            cache: Box::new(LruCache::<XorName, ImmutableData>::new(config.cache_size)),
+            custom_cache: LruCache::new(CUSTOM_CACHE_SIZE),
             timeout,
-            joiner,
+            joiner: Some(joiner),
             core_tx,
             net_tx,
+            avg_round_trip: None,
+            stats: Stats::default(),
+            audit_log: None,
+            limiter: RequestLimiter::default(),
+            metrics_txs: Vec::new(),
+            network_txs: Vec::new(),
+            retry_policy: RetryPolicy::default(),
+            balance_check: false,
+            balance: None,
+            negative_cache: HashMap::new(),
+            negative_cache_ttl: config.negative_cache_ttl,
+            reconnect_attempts: 0,
+            routing_since: None,
+            connection_state: ConnectionState::Connected,
         }
     }
+
+    // Send the metric for a completed request to all metrics streams, dropping closed ones.
+    fn emit_metric(&mut self, event: &CoreEvent, latency: Duration) {
+        if self.metrics_txs.is_empty() {
+            return;
+        }
+
+        if let Some(metric) = metrics::request_completed(event, latency) {
+            self.metrics_txs
+                .retain(|tx| tx.send(metric.clone()).is_ok());
+        }
+    }
+
+    // Send the network event to all subscribers, dropping the ones which have gone.
+    fn emit_network_event(&mut self, event: NetworkEvent) {
+        self.network_txs
+            .retain(|tx| tx.unbounded_send(event).is_ok());
+    }
+
+    // Return the routing client, unless logged out.
+    fn routing_mut(&mut self) -> Result<&mut Routing, CoreError> {
+        self.routing.as_mut().ok_or(CoreError::OperationAborted)
+    }
+
+    // Return `true` if the immutable data was found missing within the negative cache TTL.
+    fn known_missing(&mut self, name: &XorName) -> bool {
+        let ttl = match self.negative_cache_ttl {
+            Some(ttl) => ttl,
+            None => return false,
+        };
+
+        match self.negative_cache.get(name).map(|at| at.elapsed() < ttl) {
+            Some(true) => true,
+            Some(false) => {
+                let _ = self.negative_cache.remove(name);
+                false
+            }
+            None => false,
+        }
+    }
+
+    // Remember that the immutable data is missing, dropping expired entries.
+    fn remember_missing(&mut self, name: XorName) {
+        if let Some(ttl) = self.negative_cache_ttl {
+            self.negative_cache.retain(|_, at| at.elapsed() < ttl);
+            let _ = self.negative_cache.insert(name, Instant::now());
+        }
+    }
+
+    // Add a new sample to the rolling average of request round trip times.
+    fn update_round_trip(&mut self, sample: Duration) {
+        self.avg_round_trip = Some(match self.avg_round_trip {
+            Some(avg) => (avg * (ROUND_TRIP_SMOOTHING - 1) + sample) / ROUND_TRIP_SMOOTHING,
+            None => sample,
+        });
+    }
 }
 
 /// Spawn a routing thread and run the routing event loop.
@@ -571,23 +1233,180 @@ pub fn setup_routing(
     Ok((routing, routing_rx))
 }
 
+// Get immutable data, sending the request under `msg_id` if given.
+fn fetch_idata(
+    client: &impl Client,
+    name: XorName,
+    msg_id: Option<MessageId>,
+) -> Box<CoreFuture<ImmutableData>> {
+    trace!("GetIData for {:?}", name);
+
+    let inner = client.inner();
+    let cached = inner.borrow_mut().cache.get(&name);
+    if let Some(data) = cached {
+        trace!("ImmutableData found in cache.");
+        count_request(client, |stats| stats.cache_hits += 1);
+        return future::ok(data).into_box();
+    }
+
+    let known_missing = inner.borrow_mut().known_missing(&name);
+    if known_missing {
+        trace!("ImmutableData recently found missing.");
+        return err!(CoreError::RoutingClientError(ClientError::NoSuchData));
+    }
+
+    let inner = Rc::downgrade(&client.inner());
+    let inner2 = inner.clone();
+    count_request(client, |stats| {
+        stats.cache_misses += 1;
+        stats.idata_gets += 1;
+    });
+    send_with_id(client, msg_id, move |routing, msg_id| {
+        routing.get_idata(Authority::NaeManager(name), name, msg_id)
+    })
+    .and_then(|event| match_event!(event, CoreEvent::GetIData))
+    .map(move |data| {
+        if let Some(inner) = inner.upgrade() {
+            // Put to cache
+            inner.borrow_mut().cache.insert(*data.name(), data.clone());
+        }
+        data
+    })
+    .map_err(move |error| {
+        if let CoreError::RoutingClientError(ClientError::NoSuchData) = error {
+            if let Some(inner) = inner2.upgrade() {
+                inner.borrow_mut().remember_missing(name);
+            }
+        }
+        error
+    })
+    .into_box()
+}
+
+// Handle the loss of the connection of the routing event loop started at `started_at`: fail the
+// requests waiting for a response and, if enabled, restart routing.
+fn handle_disconnect(client: &impl Client, started_at: Instant) -> Option<TailFuture> {
+    let inner = client.inner();
+    let max_attempts = {
+        let mut inner = inner.borrow_mut();
+
+        // Ignore the disconnection of routing which has already been replaced.
+        if inner
+            .routing_since
+            .map_or(false, |since| started_at < since)
+        {
+            return None;
+        }
+
+        for (_, hook) in inner.hooks.drain() {
+            let _ = hook.send(CoreEvent::ConnectionLost);
+        }
+        inner.emit_network_event(NetworkEvent::Disconnected);
+
+        inner.connection_state = if inner.reconnect_attempts > 0 {
+            ConnectionState::Reconnecting
+        } else {
+            ConnectionState::Disconnected
+        };
+        inner.reconnect_attempts
+    };
+
+    if max_attempts == 0 {
+        return None;
+    }
+
+    let policy = RetryPolicy {
+        max_attempts,
+        ..client.retry_policy()
+    };
+    let client = client.clone();
+
+    let fut = future::loop_fn(1, move |attempts| match client.restart_routing() {
+        Ok(()) => Either::A(future::ok(Loop::Break(()))),
+        // Logged out while waiting to reconnect.
+        Err(_) if client.connection_state() == ConnectionState::LoggedOut => {
+            Either::A(future::ok(Loop::Break(())))
+        }
+        Err(error) if policy.should_retry(attempts) => {
+            debug!("Reconnection attempt {} failed: {:?}", attempts, error);
+            Either::B(
+                retry::backoff(&client, &policy, attempts)
+                    .map(move |()| Loop::Continue(attempts + 1)),
+            )
+        }
+        Err(error) => {
+            warn!("Could not reconnect to the network: {:?}", error);
+            let inner = client.inner();
+            inner.borrow_mut().connection_state = ConnectionState::Disconnected;
+            Either::A(future::ok(Loop::Break(())))
+        }
+    })
+    .map_err(|error| warn!("Could not reconnect to the network: {:?}", error));
+
+    Some(Box::new(fut))
+}
+
 /// Send a request and return a future that resolves to the response.
 fn send<F>(client: &impl Client, req: F) -> Box<CoreFuture<CoreEvent>>
+where
+    F: Fn(&mut Routing, MessageId) -> Result<(), InterfaceError> + 'static,
+{
+    send_with_id(client, None, req)
+}
+
+// Send a request under `msg_id` if given, or under a new message id otherwise. Retries after the
+// rate limit is exceeded are always sent under a new message id.
+fn send_with_id<F>(
+    client: &impl Client,
+    msg_id: Option<MessageId>,
+    req: F,
+) -> Box<CoreFuture<CoreEvent>>
 where
     F: Fn(&mut Routing, MessageId) -> Result<(), InterfaceError> + 'static,
 {
     let inner = Rc::downgrade(&client.inner());
+    let first_msg_id = Cell::new(msg_id);
     let func = move |_| {
         if let Some(inner) = inner.upgrade() {
-            let msg_id = MessageId::new();
-            if let Err(error) = req(&mut inner.borrow_mut().routing, msg_id) {
-                return future::err(CoreError::from(error)).into_box();
+            let msg_id = first_msg_id.take().unwrap_or_else(new_message_id);
+            let result = inner
+                .borrow_mut()
+                .routing_mut()
+                .and_then(|routing| req(routing, msg_id).map_err(CoreError::from));
+            if let Err(error) = result {
+                return future::err(error).into_box();
             }
 
             let (hook, rx) = oneshot::channel();
-            let _ = inner.borrow_mut().hooks.insert(msg_id, hook);
+            if inner.borrow_mut().hooks.insert(msg_id, hook).is_some() {
+                // Dropping the displaced hook fails the earlier request with `OperationAborted`
+                // instead of leaving it waiting for a response it will never get.
+                error!(
+                    "Duplicate message id {:?}, aborting the earlier request",
+                    msg_id
+                );
+            }
 
-            let rx = rx.map_err(|_| CoreError::OperationAborted);
+            let sent_at = Instant::now();
+            let inner_weak = Rc::downgrade(&inner);
+
+            let rx = rx
+                .map_err(|_| CoreError::OperationAborted)
+                .and_then(move |event| {
+                    match event {
+                        CoreEvent::RequestCancelled => return Err(CoreError::RequestCancelled),
+                        CoreEvent::ConnectionLost => return Err(CoreError::ConnectionLost),
+                        _ => (),
+                    }
+
+                    if let Some(inner) = inner_weak.upgrade() {
+                        let latency = sent_at.elapsed();
+                        let mut inner = inner.borrow_mut();
+                        inner.update_round_trip(latency);
+                        inner.emit_metric(&event, latency);
+                    }
+                    Ok(event)
+                });
             let rx = setup_timeout_and_retry_delay(&inner, msg_id, rx);
             let rx = rx.map(|event| {
                 if let CoreEvent::RateLimitExceeded = event {
@@ -602,18 +1421,179 @@ where
         }
     };
 
-    future::loop_fn((), func).into_box()
+    let limiter = client.inner().borrow().limiter.clone();
+    let run = move |slot| {
+        future::loop_fn((), func).then(move |res| {
+            drop(slot);
+            res
+        })
+    };
+
+    // Send straight away if the limit on requests in flight allows it, as callers may rely on the
+    // request being sent before the future is polled.
+    match limiter.try_acquire() {
+        Some(slot) => run(slot).into_box(),
+        None => limiter.acquire().and_then(run).into_box(),
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+thread_local! {
+    static MESSAGE_ID_SEQUENCE: Cell<Option<u64>> = Cell::new(None);
 }
 
-/// Sends a mutation request.
-fn send_mutation<F>(client: &impl Client, req: F) -> Box<CoreFuture<()>>
+/// Make the clients running on the current thread use a deterministic sequence of message ids,
+/// starting with `deterministic_message_id(start)`. Pass `None` to go back to random ids.
+#[cfg(any(test, feature = "testing"))]
+#[doc(hidden)]
+pub fn set_message_id_sequence(start: Option<u64>) {
+    MESSAGE_ID_SEQUENCE.with(|sequence| sequence.set(start));
+}
+
+/// Return the message id at position `index` of the deterministic sequence.
+#[cfg(any(test, feature = "testing"))]
+#[doc(hidden)]
+pub fn deterministic_message_id(index: u64) -> MessageId {
+    let mut name = [0; XOR_NAME_LEN];
+    for (pos, byte) in name.iter_mut().take(8).enumerate() {
+        *byte = (index >> (8 * (7 - pos))) as u8;
+    }
+
+    // `MessageId` can only be constructed randomly, but it serialises as the wrapped `XorName`.
+    unwrap!(deserialise(&unwrap!(serialise(&XorName(name)))))
+}
+
+// Generate the id for a new request.
+fn new_message_id() -> MessageId {
+    #[cfg(any(test, feature = "testing"))]
+    {
+        let index = MESSAGE_ID_SEQUENCE.with(|sequence| {
+            let index = sequence.get();
+            sequence.set(index.map(|index| index + 1));
+            index
+        });
+
+        if let Some(index) = index {
+            return deterministic_message_id(index);
+        }
+    }
+
+    MessageId::new()
+}
+
+// Record a request that is about to be sent in the client's stats.
+fn count_request<F>(client: &impl Client, f: F)
+where
+    F: FnOnce(&mut Stats),
+{
+    let inner = client.inner();
+    f(&mut inner.borrow_mut().stats);
+}
+
+/// Sends a mutation request, recording it in the audit log if that's enabled.
+fn send_mutation<F>(
+    client: &impl Client,
+    operation: &'static str,
+    data_name: Option<XorName>,
+    req: F,
+) -> Box<CoreFuture<()>>
 where
     F: Fn(&mut Routing, Authority<XorName>, MessageId) -> Result<(), InterfaceError> + 'static,
 {
     let dst = some_or_err!(client.cm_addr());
 
-    send(client, move |routing, msg_id| req(routing, dst, msg_id))
-        .and_then(|event| match_event!(event, CoreEvent::Mutation))
+    if client.inner().borrow().balance_check {
+        let client = client.clone();
+        return check_balance(&client)
+            .and_then(move |()| send_checked_mutation(&client, operation, data_name, dst, req))
+            .into_box();
+    }
+
+    send_checked_mutation(client, operation, data_name, dst, req)
+}
+
+fn send_checked_mutation<F>(
+    client: &impl Client,
+    operation: &'static str,
+    data_name: Option<XorName>,
+    dst: Authority<XorName>,
+    req: F,
+) -> Box<CoreFuture<()>>
+where
+    F: Fn(&mut Routing, Authority<XorName>, MessageId) -> Result<(), InterfaceError> + 'static,
+{
+    let last_msg_id = Rc::new(Cell::new(None));
+    let last_msg_id2 = Rc::clone(&last_msg_id);
+    let inner = Rc::downgrade(&client.inner());
+
+    send(client, move |routing, msg_id| {
+        last_msg_id2.set(Some(msg_id));
+        req(routing, dst, msg_id)
+    })
+    .and_then(|event| match_event!(event, CoreEvent::Mutation))
+    .then(move |result| {
+        if let (Some(inner), Some(msg_id)) = (inner.upgrade(), last_msg_id.get()) {
+            let mut inner = inner.borrow_mut();
+
+            if result.is_ok() {
+                if let Some((ref mut available, _)) = inner.balance {
+                    *available = available.saturating_sub(1);
+                }
+            }
+
+            if let Some(ref mut audit_log) = inner.audit_log {
+                audit_log.push(AuditEntry {
+                    operation,
+                    data_name,
+                    msg_id,
+                    result: result.as_ref().map(|_| ()).map_err(|err| err.to_string()),
+                });
+            }
+        }
+        result
+    })
+    .into_box()
+}
+
+// Fail with `InsufficientBalance` if the account can't afford a mutation, fetching the balance
+// from the network if it's not known or too old.
+fn check_balance(client: &impl Client) -> Box<CoreFuture<()>> {
+    let cached = {
+        let inner = client.inner();
+        let balance = inner.borrow().balance;
+        balance
+            .filter(|&(_, fetched_at)| {
+                fetched_at.elapsed() < Duration::from_secs(BALANCE_REFRESH_SECS)
+            })
+            .map(|(available, _)| available)
+    };
+
+    let available = if let Some(available) = cached {
+        ok!(available)
+    } else {
+        let inner = Rc::downgrade(&client.inner());
+        client
+            .get_account_info()
+            .map(move |info| {
+                if let Some(inner) = inner.upgrade() {
+                    inner.borrow_mut().balance = Some((info.mutations_available, Instant::now()));
+                }
+                info.mutations_available
+            })
+            .into_box()
+    };
+
+    available
+        .and_then(|available| {
+            if available == 0 {
+                Err(CoreError::InsufficientBalance {
+                    needed: 1,
+                    available,
+                })
+            } else {
+                Ok(())
+            }
+        })
         .into_box()
 }
 
@@ -690,3 +1670,1301 @@ type TimeoutFuture = Either<
     FutureResult<CoreEvent, CoreError>,
     Then<Timeout, Result<CoreEvent, CoreError>, fn(io::Result<()>) -> Result<CoreEvent, CoreError>>,
 >;
+
+#[cfg(all(test, feature = "mock-network"))]
+mod tests_with_mock_routing {
+    use super::*;
+    use crate::utils;
+    use crate::utils::test_utils::random_client;
+    use rand;
+    use routing::{Request, Response};
+    use tiny_keccak::sha3_256;
+
+    // Test fetching versions of several `MutableData` at once, some of which don't exist.
+    #[test]
+    fn get_mdata_versions_of_multiple_data() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            let tag = 10_000;
+            let owners = btree_set![unwrap!(client.public_signing_key())];
+
+            let data0 = unwrap!(MutableData::new(
+                rand::random(),
+                tag,
+                Default::default(),
+                Default::default(),
+                owners.clone(),
+            ));
+            let data1 = unwrap!(MutableData::new(
+                rand::random(),
+                tag,
+                Default::default(),
+                Default::default(),
+                owners,
+            ));
+
+            let ids = vec![
+                (*data0.name(), tag),
+                (rand::random(), tag),
+                (*data1.name(), tag),
+            ];
+
+            client
+                .put_mdata(data0)
+                .join(client.put_mdata(data1))
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.get_mdata_versions(ids)
+                })
+                .then(|res| {
+                    assert_eq!(unwrap!(res), vec![Some(0), None, Some(0)]);
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test putting several `MutableData` at once, some of which already exist.
+    #[test]
+    fn put_mdata_many() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            let tag = 10_000;
+            let owners = btree_set![unwrap!(client.public_signing_key())];
+
+            let data: Vec<_> = (0..4)
+                .map(|_| {
+                    unwrap!(MutableData::new(
+                        rand::random(),
+                        tag,
+                        Default::default(),
+                        Default::default(),
+                        owners.clone(),
+                    ))
+                })
+                .collect();
+            let existing = vec![data[1].clone(), data[3].clone()];
+
+            client
+                .put_mdata_many(existing)
+                .then(move |res| {
+                    assert!(unwrap!(res).iter().all(Result::is_ok));
+
+                    let mutations = client2.stats().mdata_mutations;
+                    client2
+                        .put_mdata_many(data)
+                        .map(move |res| (res, mutations))
+                })
+                .then(move |res| {
+                    let (res, mutations) = unwrap!(res);
+                    assert_eq!(res.len(), 4);
+
+                    for (index, res) in res.into_iter().enumerate() {
+                        match (index % 2, res) {
+                            (0, Ok(())) => (),
+                            (1, Err(CoreError::RoutingClientError(ClientError::DataExists))) => (),
+                            (_, res) => panic!("Unexpected result at {}: {:?}", index, res),
+                        }
+                    }
+                    assert_eq!(client3.stats().mdata_mutations - mutations, 4);
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test checking whether `MutableData` entries have been deleted.
+    #[test]
+    fn mdata_entry_deletion_check() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+            let client5 = client.clone();
+
+            let tag = 10_000;
+            let data = unwrap!(MutableData::new(
+                rand::random(),
+                tag,
+                Default::default(),
+                btree_map![
+                    vec![0] => Value {
+                        content: vec![0],
+                        entry_version: 0,
+                    },
+                    vec![1] => Value {
+                        content: vec![1],
+                        entry_version: 0,
+                    }
+                ],
+                btree_set![unwrap!(client.public_signing_key())],
+            ));
+            let name = *data.name();
+
+            client
+                .put_mdata(data)
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.mutate_mdata_entries(
+                        name,
+                        tag,
+                        btree_map![vec![1] => EntryAction::Del(1)],
+                    )
+                })
+                .then(move |res| {
+                    unwrap!(res);
+                    client3.is_mdata_entry_deleted(name, tag, vec![0])
+                })
+                .then(move |res| {
+                    assert!(!unwrap!(res));
+                    client4.is_mdata_entry_deleted(name, tag, vec![1])
+                })
+                .then(move |res| {
+                    assert!(unwrap!(res));
+                    client5.is_mdata_entry_deleted(name, tag, vec![2])
+                })
+                .then(|res| {
+                    match res {
+                        Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)) => (),
+                        res => panic!("Unexpected result: {:?}", res),
+                    }
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test that the average round trip time is tracked once responses are received.
+    #[test]
+    fn round_trip_time() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            assert!(client.avg_round_trip().is_none());
+
+            client.get_idata(rand::random()).then(move |_| {
+                assert!(client2.avg_round_trip().is_some());
+                Ok::<_, CoreError>(())
+            })
+        });
+    }
+
+    // Test that oversized immutable data is rejected before it reaches the network.
+    #[test]
+    fn put_idata_too_large() {
+        random_client(|client| {
+            let value = unwrap!(utils::generate_random_vector(2 * 1024 * 1024));
+            let data = ImmutableData::new(value);
+
+            // Any request reaching the mock network would fail with `NetworkOther`.
+            client.set_network_limits(Some(0));
+
+            client.put_idata(data).then(|res| {
+                match res {
+                    Err(CoreError::RoutingClientError(ClientError::DataTooLarge)) => (),
+                    res => panic!("Unexpected result: {:?}", res),
+                }
+                Ok::<_, CoreError>(())
+            })
+        });
+    }
+
+    // Test fetching byte ranges of `ImmutableData`, including ranges past the end of the value.
+    #[test]
+    fn get_idata_range() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            let value = unwrap!(utils::generate_random_vector(100));
+            let value2 = value.clone();
+            let data = ImmutableData::new(value);
+            let name = *data.name();
+
+            client
+                .put_idata(data)
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.get_idata_range(name, 10, 20)
+                })
+                .then(move |res| {
+                    assert_eq!(unwrap!(res), &value2[10..30]);
+                    client3.get_idata_range(name, 90, 20)
+                })
+                .then(move |res| {
+                    assert_eq!(unwrap!(res).len(), 10);
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test checking the existence of several `ImmutableData` at once, some of which don't exist.
+    #[test]
+    fn idata_exists_many() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            let data0 = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+            let data1 = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+            let missing = rand::random();
+
+            let names = vec![*data0.name(), missing, *data1.name()];
+            let expected = vec![
+                (*data0.name(), true),
+                (missing, false),
+                (*data1.name(), true),
+            ];
+
+            client
+                .put_idata(data0)
+                .join(client.put_idata(data1))
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.idata_exists_many(names)
+                })
+                .then(move |res| {
+                    assert_eq!(unwrap!(res), expected);
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test getting several `ImmutableData` at once, one of which doesn't exist.
+    #[test]
+    fn get_idata_many() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            let data: Vec<_> = (0..4)
+                .map(|_| ImmutableData::new(unwrap!(utils::generate_random_vector(10))))
+                .collect();
+            let missing = rand::random();
+
+            let mut names: Vec<_> = data.iter().map(|data| *data.name()).collect();
+            names.insert(2, missing);
+            let names2 = names.clone();
+
+            let puts: Vec<_> = data
+                .iter()
+                .map(|data| client.put_idata(data.clone()))
+                .collect();
+
+            future::join_all(puts)
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.get_idata_many_partial(names)
+                })
+                .then(move |res| {
+                    let res = unwrap!(res);
+                    assert_eq!(res.len(), 5);
+
+                    for (index, res) in res.into_iter().enumerate() {
+                        match (index, res) {
+                            (2, Err(CoreError::RoutingClientError(ClientError::NoSuchData))) => (),
+                            (2, res) => panic!("Unexpected result: {:?}", res),
+                            (index, res) => {
+                                let expected = if index < 2 { index } else { index - 1 };
+                                assert_eq!(unwrap!(res), data[expected]);
+                            }
+                        }
+                    }
+
+                    client3.get_idata_many(names2)
+                })
+                .then(|res| {
+                    match res {
+                        Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => (),
+                        res => panic!("Unexpected result: {:?}", res),
+                    }
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test that requests are counted by the type of data they operate on.
+    #[test]
+    fn request_stats() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            let before = client.stats();
+
+            let tag = 10_000;
+            let owners = btree_set![unwrap!(client.public_signing_key())];
+            let data = unwrap!(MutableData::new(
+                rand::random(),
+                tag,
+                Default::default(),
+                Default::default(),
+                owners,
+            ));
+            let name = *data.name();
+            let idata = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+
+            client
+                .put_mdata(data)
+                .join(client.put_idata(idata))
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.get_mdata_version(name, tag)
+                })
+                .then(move |res| {
+                    let _ = unwrap!(res);
+
+                    let after = client3.stats();
+                    assert_eq!(after.idata_puts - before.idata_puts, 1);
+                    assert_eq!(after.mdata_mutations - before.mdata_mutations, 1);
+                    assert_eq!(after.mdata_gets - before.mdata_gets, 1);
+                    assert_eq!(after.idata_gets, before.idata_gets);
+                    assert_eq!(after.gets() - before.gets(), 1);
+                    assert_eq!(after.mutations() - before.mutations(), 2);
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test that a cold GET counts as a cache miss and a warm one as a hit.
+    #[test]
+    fn cache_hits_and_misses() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            let data = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+            let name = *data.name();
+
+            client
+                .put_idata(data)
+                .then(move |res| {
+                    unwrap!(res);
+                    // Evict the data put to the cache, so the next GET is cold.
+                    client2.set_data_cache(Box::new(LruCache::new(IMMUT_DATA_CACHE_SIZE)));
+                    let before = (client2.cache_hits(), client2.cache_misses());
+                    client2.get_idata(name).map(move |_| before)
+                })
+                .then(move |res| {
+                    let (hits, misses) = unwrap!(res);
+                    assert_eq!(client3.cache_misses() - misses, 1);
+                    assert_eq!(client3.cache_hits(), hits);
+
+                    let before = client3.stats();
+                    client3.get_idata(name).map(move |_| before)
+                })
+                .then(move |res| {
+                    let before = unwrap!(res);
+                    let after = client4.stats();
+                    assert_eq!(after.cache_hits - before.cache_hits, 1);
+                    assert_eq!(after.cache_misses, before.cache_misses);
+                    assert_eq!(after.idata_gets, before.idata_gets);
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test that a request which is never answered times out and doesn't leak its hook.
+    #[test]
+    fn request_timeout_removes_hook() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            client.set_timeout(Duration::from_millis(250));
+            client.set_simulate_timeout(true);
+            let started_at = Instant::now();
+
+            client.get_idata(rand::random()).then(move |res| {
+                match res {
+                    Err(CoreError::RequestTimeout) => (),
+                    res => panic!("Unexpected result: {:?}", res),
+                }
+                assert!(started_at.elapsed() < Duration::from_secs(5));
+                assert!(client2.inner().borrow().hooks.is_empty());
+                Ok::<_, CoreError>(())
+            })
+        });
+    }
+
+    // Test that a response delayed past the request timeout makes the request time out, and that
+    // removing the latency lets responses arrive in time again.
+    #[test]
+    fn request_latency() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            client.set_timeout(Duration::from_millis(250));
+            client.with_routing(|routing| {
+                routing.set_request_latency(Some(Duration::from_millis(1000)))
+            });
+
+            client
+                .get_idata(rand::random())
+                .then(move |res| {
+                    match res {
+                        Err(CoreError::RequestTimeout) => (),
+                        res => panic!("Unexpected result: {:?}", res),
+                    }
+
+                    client2.with_routing(|routing| routing.set_request_latency(None));
+
+                    client2.get_idata(rand::random())
+                })
+                .then(|res| {
+                    match res {
+                        Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => (),
+                        res => panic!("Unexpected result: {:?}", res),
+                    }
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test that a missing immutable data is remembered, and forgotten once it's put.
+    #[test]
+    fn negative_cache() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            let data = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+            let name = *data.name();
+
+            client
+                .get_idata(name)
+                .then(move |res| {
+                    match res {
+                        Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => (),
+                        res => panic!("Unexpected result: {:?}", res),
+                    }
+
+                    let gets = client2.stats().idata_gets;
+                    client2.get_idata(name).then(move |res| Ok((res, gets)))
+                })
+                .then(move |res: Result<_, CoreError>| {
+                    let (res, gets) = unwrap!(res);
+                    match res {
+                        Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => (),
+                        res => panic!("Unexpected result: {:?}", res),
+                    }
+                    // The second probe didn't reach the network.
+                    assert_eq!(client3.stats().idata_gets, gets);
+
+                    client3.put_idata(data)
+                })
+                .then(move |res| {
+                    unwrap!(res);
+                    // Make sure the data is fetched from the network again.
+                    client4.set_data_cache(Box::new(LruCache::new(IMMUT_DATA_CACHE_SIZE)));
+                    client4.get_idata(name)
+                })
+                .then(move |res| {
+                    assert_eq!(*unwrap!(res).name(), name);
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test storing and retrieving custom data in the client's cache.
+    #[test]
+    fn custom_cache() {
+        random_client(|client| {
+            assert_eq!(client.cache_get_custom("key"), None);
+
+            client.cache_put_custom("key".to_string(), vec![1, 2, 3]);
+            assert_eq!(client.cache_get_custom("key"), Some(vec![1, 2, 3]));
+
+            client.cache_put_custom("key".to_string(), vec![4]);
+            assert_eq!(client.cache_get_custom("key"), Some(vec![4]));
+            assert_eq!(client.cache_get_custom("other"), None);
+
+            Ok::<_, CoreError>(())
+        });
+    }
+
+    // Test that the audit log records the most recent mutations along with their outcome.
+    #[test]
+    fn audit_log() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            assert!(client.audit_log().is_empty());
+            client.enable_audit_log(2);
+
+            let data0 = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+            let data1 = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+            let data1_name = *data1.name();
+            let missing_name = rand::random();
+
+            client
+                .put_idata(data0)
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.put_idata(data1)
+                })
+                .then(move |res| {
+                    unwrap!(res);
+                    client3.mutate_mdata_entries(missing_name, 10_000, Default::default())
+                })
+                .then(move |res| {
+                    assert!(res.is_err());
+
+                    let log = client4.audit_log();
+                    assert_eq!(log.len(), 2);
+
+                    assert_eq!(log[0].operation, "PutIData");
+                    assert_eq!(log[0].data_name, Some(data1_name));
+                    assert_eq!(log[0].result, Ok(()));
+
+                    assert_eq!(log[1].operation, "MutateMDataEntries");
+                    assert_eq!(log[1].data_name, Some(missing_name));
+                    assert!(log[1].result.is_err());
+                    assert_ne!(log[0].msg_id, log[1].msg_id);
+
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test that a custom data cache replaces the default one.
+    #[test]
+    fn custom_data_cache() {
+        // Cache which never holds anything, so every GET goes to the network.
+        struct NoCache;
+
+        impl DataCache for NoCache {
+            fn get(&mut self, _name: &XorName) -> Option<ImmutableData> {
+                None
+            }
+
+            fn insert(&mut self, _name: XorName, _data: ImmutableData) {}
+
+            fn remove(&mut self, _name: &XorName) {}
+        }
+
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            client.set_data_cache(Box::new(NoCache));
+
+            let data = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+            let name = *data.name();
+
+            client
+                .put_idata(data)
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.get_idata(name)
+                })
+                .then(move |res| {
+                    let _ = unwrap!(res);
+                    let gets = client3.stats().idata_gets;
+                    client3.get_idata(name).map(move |_| gets)
+                })
+                .then(move |res| {
+                    let gets = unwrap!(res);
+                    assert_eq!(client4.stats().idata_gets, gets + 1);
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test that the network identity is the hash of the account's public signing key.
+    #[test]
+    fn network_identity() {
+        random_client(|client| {
+            let sign_pk = unwrap!(client.public_signing_key());
+            let expected = XorName(sha3_256(&sign_pk.0));
+
+            assert_eq!(unwrap!(client.network_identity()), expected);
+            Ok::<_, CoreError>(())
+        });
+    }
+
+    // Test encrypting data for a recipient and that tampered data fails to decrypt.
+    #[test]
+    fn encrypt_and_decrypt() {
+        random_client(|client| {
+            let public_key = unwrap!(client.public_encryption_key());
+            let plain_text = unwrap!(utils::generate_random_vector::<u8>(10));
+
+            let cipher_text = unwrap!(client.encrypt(&plain_text, &public_key));
+            assert_eq!(
+                unwrap!(client.decrypt(&cipher_text, &public_key)),
+                plain_text
+            );
+
+            let mut tampered = cipher_text.clone();
+            let last = tampered.len() - 1;
+            tampered[last] ^= 1;
+            match client.decrypt(&tampered, &public_key) {
+                Err(CoreError::AsymmetricDecipherFailure) => (),
+                res => panic!("Unexpected result: {:?}", res),
+            }
+
+            Ok::<_, CoreError>(())
+        });
+    }
+
+    // Test anonymous encryption for a recipient, which only the recipient can decrypt.
+    #[test]
+    fn seal_and_open() {
+        use crate::client::core_client::CoreClient;
+
+        random_client(|client| {
+            let public_key = unwrap!(client.public_encryption_key());
+            let plain_text = unwrap!(utils::generate_random_vector::<u8>(10));
+
+            let cipher_text = CoreClient::seal(&plain_text, &public_key);
+            assert_eq!(unwrap!(client.seal_open(&cipher_text)), plain_text);
+
+            let (other_key, _) = box_::gen_keypair();
+            let cipher_text = CoreClient::seal(&plain_text, &other_key);
+            match client.seal_open(&cipher_text) {
+                Err(CoreError::AsymmetricDecipherFailure) => (),
+                res => panic!("Unexpected result: {:?}", res),
+            }
+
+            Ok::<_, CoreError>(())
+        });
+    }
+
+    // Test signing data and verifying the signature against the right and wrong keys and data.
+    #[test]
+    fn sign_and_verify() {
+        use crate::client::core_client::CoreClient;
+
+        random_client(|client| {
+            let public_key = unwrap!(client.public_signing_key());
+            let data = unwrap!(utils::generate_random_vector::<u8>(10));
+
+            let signature = unwrap!(client.sign(&data));
+            assert!(CoreClient::verify(&data, &signature, &public_key));
+
+            let (other_key, _) = sign::gen_keypair();
+            assert!(!CoreClient::verify(&data, &signature, &other_key));
+
+            let mut mutated = data.clone();
+            mutated[0] ^= 1;
+            assert!(!CoreClient::verify(&mutated, &signature, &public_key));
+
+            assert!(!CoreClient::verify(&data, &signature[1..], &public_key));
+
+            Ok::<_, CoreError>(())
+        });
+    }
+
+    // Test that data just put is read back from the cache, without a network request.
+    #[test]
+    fn put_idata_caches() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            let data = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+            let data2 = data.clone();
+
+            client
+                .put_idata(data)
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.set_network_limits(Some(0));
+                    client2
+                        .get_idata(*data2.name())
+                        .map(move |got| (got, data2))
+                })
+                .then(move |res| {
+                    let (got, data) = unwrap!(res);
+                    assert_eq!(got, data);
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test that requests over the in-flight limit wait for earlier ones to complete.
+    #[test]
+    fn max_in_flight() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            client.set_max_in_flight(Some(1));
+
+            let futures: Vec<_> = (0..3)
+                .map(|_| {
+                    client
+                        .get_idata(rand::random())
+                        .then(|res| Ok::<_, CoreError>(res.is_err()))
+                })
+                .collect();
+
+            // Only the first request has been sent, the others are waiting.
+            assert_eq!(client.inner().borrow().limiter.in_flight(), 1);
+
+            future::join_all(futures).then(move |res| {
+                assert_eq!(unwrap!(res), vec![true, true, true]);
+                assert_eq!(client2.inner().borrow().limiter.in_flight(), 0);
+                Ok::<_, CoreError>(())
+            })
+        });
+    }
+
+    // Test that completed requests are reported on the metrics stream.
+    #[test]
+    fn metrics_stream() {
+        let rx = random_client(|client| {
+            let rx = client.metrics_stream();
+            let data = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+
+            client
+                .put_idata(data)
+                .join(client.get_idata(rand::random()).then(|res| {
+                    assert!(res.is_err());
+                    Ok::<_, CoreError>(())
+                }))
+                .map(move |_| rx)
+        });
+
+        let metrics: Vec<_> = rx.try_iter().collect();
+        assert_eq!(metrics.len(), 2);
+
+        for metric in metrics {
+            match metric {
+                MetricEvent::RequestCompleted {
+                    op: "Mutation",
+                    result,
+                    ..
+                } => assert_eq!(result, Ok(())),
+                MetricEvent::RequestCompleted {
+                    op: "GetIData",
+                    bytes,
+                    result,
+                    ..
+                } => {
+                    assert_eq!(bytes, 0);
+                    assert!(result.is_err());
+                }
+                metric => panic!("Unexpected metric: {:?}", metric),
+            }
+        }
+    }
+
+    // Test that the storage usage grows with every mutation.
+    #[test]
+    fn storage_usage_percent() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            let data = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+
+            client
+                .storage_usage_percent()
+                .then(move |res| {
+                    let before = unwrap!(res);
+                    client2.put_idata(data).map(move |()| before)
+                })
+                .then(move |res| {
+                    let before = unwrap!(res);
+                    client3
+                        .storage_usage_percent()
+                        .map(move |after| (before, after))
+                })
+                .then(|res| {
+                    let (before, after) = unwrap!(res);
+                    assert!(before >= 0.0);
+                    assert!(after > before);
+                    assert!(after <= 100.0);
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test that batch results are in input order even when the responses arrive in a different
+    // order, including for failed items.
+    #[test]
+    fn batch_results_keep_input_order() {
+        random_client(|client| {
+            let data: Vec<_> = (0..3u8).map(|i| ImmutableData::new(vec![i; 10])).collect();
+            let stored: HashMap<_, _> = data
+                .iter()
+                .map(|data| (*data.name(), data.clone()))
+                .collect();
+
+            let missing = rand::random();
+            let names = vec![*data[0].name(), missing, *data[1].name(), *data[2].name()];
+            let expected = vec![
+                (*data[0].name(), true),
+                (missing, false),
+                (*data[1].name(), true),
+                (*data[2].name(), true),
+            ];
+
+            client.with_routing(|routing| {
+                routing.set_request_hook(move |req| match *req {
+                    Request::GetIData { name, msg_id } => Some(Response::GetIData {
+                        res: stored.get(&name).cloned().ok_or(ClientError::NoSuchData),
+                        msg_id,
+                    }),
+                    _ => None,
+                });
+
+                // Deliver the responses for earlier data later, while the failure arrives first.
+                routing.set_response_delay_hook(|res| match *res {
+                    Response::GetIData {
+                        res: Ok(ref data), ..
+                    } => 300 - 100 * u64::from(data.value()[0]),
+                    _ => 0,
+                });
+            });
+
+            client.idata_exists_many(names).then(move |res| {
+                assert_eq!(unwrap!(res), expected);
+                Ok::<_, CoreError>(())
+            })
+        });
+    }
+
+    // Test that the estimated cost of an upload matches what the network charges for it.
+    #[test]
+    fn estimate_cost() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            let value = unwrap!(utils::generate_random_vector(4096));
+            let expected = client.estimate_cost(&[
+                PlannedOp::UploadContent {
+                    size: value.len() as u64,
+                },
+                PlannedOp::PutMData,
+            ]);
+
+            client
+                .get_account_info()
+                .then(move |res| {
+                    let before = unwrap!(res);
+                    crate::immutable_data::create(&client2, &value, None)
+                        .and_then(move |data| client2.put_idata(data))
+                        .map(move |()| before)
+                })
+                .then(move |res| {
+                    let before = unwrap!(res);
+                    let owners = btree_set![unwrap!(client3.public_signing_key())];
+                    let data = unwrap!(MutableData::new(
+                        rand::random(),
+                        10_000,
+                        Default::default(),
+                        Default::default(),
+                        owners,
+                    ));
+                    client3.put_mdata(data).map(move |()| before)
+                })
+                .then(move |res| {
+                    let before = unwrap!(res);
+                    client4.get_account_info().map(move |after| (before, after))
+                })
+                .then(move |res| {
+                    let (before, after) = unwrap!(res);
+                    assert_eq!(after.mutations_done - before.mutations_done, expected);
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test that operations with recovery give up after the attempts allowed by the retry policy.
+    #[test]
+    fn retry_policy() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            let policy = RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::from_millis(10),
+                max_delay: Duration::from_millis(20),
+                jitter: false,
+            };
+            client.set_default_retry_policy(policy);
+            assert_eq!(client.retry_policy(), policy);
+
+            client.set_timeout(Duration::from_millis(100));
+            client.set_simulate_timeout(true);
+            let mutations = client.stats().mdata_mutations;
+
+            recovery::mutate_mdata_entries(client, rand::random(), 10_000, BTreeMap::new()).then(
+                move |res| {
+                    match res {
+                        Err(CoreError::RequestTimeout) => (),
+                        res => panic!("Unexpected result: {:?}", res),
+                    }
+                    assert_eq!(client2.stats().mdata_mutations - mutations, 3);
+                    Ok::<_, CoreError>(())
+                },
+            )
+        });
+    }
+
+    // Test that mutations the account can't afford fail locally once the balance check is on.
+    #[test]
+    fn balance_check() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            client.with_routing(|routing| {
+                // Pretend the account can only afford a single mutation.
+                routing.set_request_hook(|req| match *req {
+                    Request::GetAccountInfo(msg_id) => Some(Response::GetAccountInfo {
+                        res: Ok(AccountInfo {
+                            mutations_done: 0,
+                            mutations_available: 1,
+                        }),
+                        msg_id,
+                    }),
+                    _ => None,
+                });
+            });
+
+            client.set_balance_check(true);
+
+            let data0 = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+            let data1 = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+            let name1 = *data1.name();
+
+            client
+                .put_idata(data0)
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.put_idata(data1)
+                })
+                .then(move |res| {
+                    match res {
+                        Err(CoreError::InsufficientBalance {
+                            needed: 1,
+                            available: 0,
+                        }) => (),
+                        res => panic!("Unexpected result: {:?}", res),
+                    }
+
+                    // The rejected data never reached the network.
+                    client3.get_idata(name1)
+                })
+                .then(|res| {
+                    match res {
+                        Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => (),
+                        res => panic!("Unexpected result: {:?}", res),
+                    }
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test that the health check succeeds while the network responds and fails once it doesn't.
+    #[test]
+    fn health_check() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            client
+                .health_check()
+                .then(move |res| {
+                    let _latency = unwrap!(res);
+
+                    client2.set_timeout(Duration::from_millis(250));
+                    client2.set_simulate_timeout(true);
+                    client2.health_check()
+                })
+                .then(|res| {
+                    match res {
+                        Err(CoreError::RequestTimeout) => (),
+                        res => panic!("Unexpected result: {:?}", res),
+                    }
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test that the health check of an unregistered client treats the missing sentinel data as a
+    // successful response, and that it still has to reach the network to succeed.
+    #[test]
+    fn health_check_unregistered() {
+        use crate::client::core_client::CoreClient;
+        use crate::utils::test_utils::setup_client;
+
+        setup_client(
+            &(),
+            |el_h, core_tx, net_tx| CoreClient::unregistered(el_h, core_tx, net_tx),
+            |client| {
+                assert!(!client.is_registered());
+                let client2 = client.clone();
+
+                client
+                    .health_check()
+                    .then(move |res| {
+                        let _latency = unwrap!(res);
+
+                        client2.set_timeout(Duration::from_millis(250));
+                        client2.set_simulate_timeout(true);
+                        client2.health_check()
+                    })
+                    .then(|res| {
+                        match res {
+                            Err(CoreError::RequestTimeout) => (),
+                            res => panic!("Unexpected result: {:?}", res),
+                        }
+                        Ok::<_, CoreError>(())
+                    })
+            },
+        );
+    }
+
+    // Test that the cache holds no more data than the configured size.
+    #[test]
+    fn cache_size() {
+        use crate::client::core_client::CoreClient;
+        use crate::utils::test_utils::setup_client;
+
+        let acc_locator = unwrap!(utils::generate_random_string(10));
+        let acc_password = unwrap!(utils::generate_random_string(10));
+        let config = ClientConfig {
+            cache_size: 1,
+            ..ClientConfig::default()
+        };
+
+        setup_client(
+            &(),
+            |el_h, core_tx, net_tx| {
+                CoreClient::new_with_config(
+                    &acc_locator,
+                    &acc_password,
+                    "",
+                    el_h,
+                    core_tx,
+                    net_tx,
+                    config,
+                )
+            },
+            |client| {
+                let client2 = client.clone();
+                let client3 = client.clone();
+                let client4 = client.clone();
+
+                let data0 = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+                let data1 = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+                let name0 = *data0.name();
+                let name1 = *data1.name();
+
+                client
+                    .put_idata(data0)
+                    .then(move |res| {
+                        unwrap!(res);
+                        client2.put_idata(data1)
+                    })
+                    .then(move |res| {
+                        unwrap!(res);
+                        // Only the cache can serve requests from now on.
+                        client3.set_network_limits(Some(0));
+                        client3.get_idata(name1)
+                    })
+                    .then(move |res| {
+                        let _ = unwrap!(res);
+                        client4.get_idata(name0)
+                    })
+                    .then(|res| {
+                        assert!(res.is_err());
+                        Ok::<_, CoreError>(())
+                    })
+            },
+        );
+    }
+
+    // Test cancelling a request which hasn't been answered yet.
+    #[test]
+    fn cancel_request() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            client.set_simulate_timeout(true);
+            let (msg_id, fut) = client.get_idata_with_id(rand::random());
+
+            assert!(client.cancel_request(&msg_id));
+            assert!(!client.cancel_request(&msg_id));
+
+            fut.then(move |res| {
+                match res {
+                    Err(CoreError::RequestCancelled) => (),
+                    res => panic!("Unexpected result: {:?}", res),
+                }
+                assert!(client2.inner().borrow().hooks.is_empty());
+                Ok::<_, CoreError>(())
+            })
+        });
+    }
+
+    // Test that the client reconnects by itself after losing the connection, failing the requests
+    // waiting for a response.
+    #[test]
+    fn auto_reconnect() {
+        use crate::utils::test_utils::random_client_with_net_obs;
+
+        let (connected_tx, connected_rx) = oneshot::channel();
+        let mut connected_tx = Some(connected_tx);
+
+        random_client_with_net_obs(
+            move |net_event| {
+                if let NetworkEvent::Connected = net_event {
+                    if let Some(tx) = connected_tx.take() {
+                        let _ = tx.send(());
+                    }
+                }
+            },
+            move |client| {
+                let client2 = client.clone();
+
+                client.set_auto_reconnect(3);
+                client.set_simulate_timeout(true);
+                let pending = client.get_idata(rand::random());
+                client.simulate_network_disconnect();
+
+                pending
+                    .then(move |res| {
+                        match res {
+                            Err(CoreError::ConnectionLost) => (),
+                            res => panic!("Unexpected result: {:?}", res),
+                        }
+                        connected_rx.map_err(|_| CoreError::OperationAborted)
+                    })
+                    .and_then(move |()| {
+                        assert_eq!(client2.connection_state(), ConnectionState::Connected);
+                        client2.get_idata(rand::random())
+                    })
+                    .then(|res| {
+                        match res {
+                            Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => (),
+                            res => panic!("Unexpected result: {:?}", res),
+                        }
+                        Ok::<_, CoreError>(())
+                    })
+            },
+        );
+    }
+
+    // Test that subscribers receive the network events in order, and that the subscriptions whose
+    // receiver is dropped are removed.
+    #[test]
+    fn subscribe_network_events() {
+        use crate::utils::test_utils::random_client_with_net_obs;
+
+        let events = random_client_with_net_obs(
+            |_| (),
+            |client| {
+                let client2 = client.clone();
+
+                let rx = client.subscribe_network_events();
+                drop(client.subscribe_network_events());
+
+                client.set_auto_reconnect(1);
+                client.simulate_network_disconnect();
+
+                rx.take(2)
+                    .collect()
+                    .map(move |events| {
+                        assert_eq!(client2.inner().borrow().network_txs.len(), 1);
+                        events
+                    })
+                    .map_err(|()| CoreError::OperationAborted)
+            },
+        );
+
+        assert_eq!(
+            events,
+            vec![NetworkEvent::Disconnected, NetworkEvent::Connected]
+        );
+    }
+
+    // Test that a newly created client reports being connected.
+    #[test]
+    fn connection_state() {
+        random_client(|client| {
+            assert_eq!(client.connection_state(), ConnectionState::Connected);
+            Ok::<_, CoreError>(())
+        });
+    }
+
+    // Test that a request whose message id is reused by another one is aborted.
+    #[test]
+    fn duplicate_message_id() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            client.set_timeout(Duration::from_millis(250));
+            client.set_simulate_timeout(true);
+
+            set_message_id_sequence(Some(0));
+            let first = client.get_idata(rand::random());
+            set_message_id_sequence(Some(0));
+            let second = client.get_idata(rand::random());
+            set_message_id_sequence(None);
+
+            assert_eq!(client.inner().borrow().hooks.len(), 1);
+
+            first
+                .then(|res| {
+                    match res {
+                        Err(CoreError::OperationAborted) => (),
+                        res => panic!("Unexpected result: {:?}", res),
+                    }
+                    second
+                })
+                .then(move |res| {
+                    match res {
+                        Err(CoreError::RequestTimeout) => (),
+                        res => panic!("Unexpected result: {:?}", res),
+                    }
+                    assert!(client2.inner().borrow().hooks.is_empty());
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+
+    // Test that requests use the deterministic message id sequence when it's set.
+    #[test]
+    fn deterministic_message_ids() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            let msg_ids = Rc::new(RefCell::new(Vec::new()));
+            let msg_ids2 = Rc::clone(&msg_ids);
+
+            client.with_routing(|routing| {
+                routing.set_request_hook(move |req| {
+                    if let Request::GetIData { msg_id, .. } = *req {
+                        msg_ids2.borrow_mut().push(msg_id);
+                    }
+                    None
+                })
+            });
+
+            set_message_id_sequence(Some(5));
+
+            client
+                .get_idata(rand::random())
+                .then(move |_| client2.get_idata(rand::random()))
+                .then(move |_| {
+                    set_message_id_sequence(None);
+
+                    assert_eq!(
+                        *msg_ids.borrow(),
+                        vec![deterministic_message_id(5), deterministic_message_id(6)]
+                    );
+                    assert_ne!(deterministic_message_id(5), deterministic_message_id(6));
+
+                    Ok::<_, CoreError>(())
+                })
+        });
+    }
+}
@@ -6,6 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use super::retry::{self, RetryPolicy};
 use super::Client;
 use crate::errors::CoreError;
 use crate::event_loop::CoreFuture;
@@ -13,12 +14,34 @@ use crate::utils::FutureExt;
 use futures::future::{self, Either, Loop};
 use futures::Future;
 use routing::{
-    Action, ClientError, EntryAction, EntryError, MutableData, PermissionSet, User, Value, XorName,
+    Action, ClientError, EntryAction, EntryError, ImmutableData, MutableData, PermissionSet, User,
+    Value, XorName,
 };
 use rust_sodium::crypto::sign;
 use std::collections::BTreeMap;
 
-const MAX_ATTEMPTS: usize = 10;
+/// Puts immutable data on the network and tries to recover from errors.
+///
+/// Immutable data is content-addressed, so if the data already exists, the same content is
+/// already stored and the put counts as successful.
+pub fn put_idata(client: &impl Client, data: ImmutableData) -> Box<CoreFuture<()>> {
+    let client = client.clone();
+    let policy = client.retry_policy();
+
+    future::loop_fn((0, ()), move |(attempts, ())| {
+        let client2 = client.clone();
+
+        client
+            .put_idata(data.clone())
+            .map(|_| Loop::Break(()))
+            .or_else(move |error| match error {
+                CoreError::RoutingClientError(ClientError::DataExists) => ok!(Loop::Break(())),
+                CoreError::RequestTimeout => retry(&client2, &policy, attempts + 1, (), error),
+                error => err!(error),
+            })
+    })
+    .into_box()
+}
 
 /// Puts mutable data on the network and tries to recover from errors.
 ///
@@ -48,30 +71,21 @@ pub fn mutate_mdata_entries(
 ) -> Box<CoreFuture<()>> {
     let state = (0, actions);
     let client = client.clone();
+    let policy = client.retry_policy();
 
     future::loop_fn(state, move |(attempts, actions)| {
+        let client2 = client.clone();
+
         client
             .mutate_mdata_entries(name, tag, actions.clone())
             .map(|_| Loop::Break(()))
             .or_else(move |error| match error {
-                CoreError::RoutingClientError(ClientError::InvalidEntryActions(errors)) => {
-                    if attempts < MAX_ATTEMPTS {
-                        let actions = fix_entry_actions(actions, &errors);
-                        Ok(Loop::Continue((attempts + 1, actions)))
-                    } else {
-                        Err(CoreError::RoutingClientError(
-                            ClientError::InvalidEntryActions(errors),
-                        ))
-                    }
-                }
-                CoreError::RequestTimeout => {
-                    if attempts < MAX_ATTEMPTS {
-                        Ok(Loop::Continue((attempts + 1, actions)))
-                    } else {
-                        Err(CoreError::RequestTimeout)
-                    }
+                CoreError::RoutingClientError(ClientError::InvalidEntryActions(ref errors)) => {
+                    let actions = fix_entry_actions(actions, errors);
+                    retry_now(&policy, attempts + 1, actions, error)
                 }
-                error => Err(error),
+                CoreError::RequestTimeout => retry(&client2, &policy, attempts + 1, actions, error),
+                error => err!(error),
             })
     })
     .into_box()
@@ -88,27 +102,20 @@ pub fn set_mdata_user_permissions(
 ) -> Box<CoreFuture<()>> {
     let state = (0, version);
     let client = client.clone();
+    let policy = client.retry_policy();
 
     future::loop_fn(state, move |(attempts, version)| {
+        let client2 = client.clone();
+
         client
             .set_mdata_user_permissions(name, tag, user, permissions, version)
             .map(|_| Loop::Break(()))
             .or_else(move |error| match error {
                 CoreError::RoutingClientError(ClientError::InvalidSuccessor(current_version)) => {
-                    if attempts < MAX_ATTEMPTS {
-                        Ok(Loop::Continue((attempts + 1, current_version + 1)))
-                    } else {
-                        Err(error)
-                    }
-                }
-                CoreError::RequestTimeout => {
-                    if attempts < MAX_ATTEMPTS {
-                        Ok(Loop::Continue((attempts + 1, version)))
-                    } else {
-                        Err(CoreError::RequestTimeout)
-                    }
+                    retry_now(&policy, attempts + 1, current_version + 1, error)
                 }
-                error => Err(error),
+                CoreError::RequestTimeout => retry(&client2, &policy, attempts + 1, version, error),
+                error => err!(error),
             })
     })
     .into_box()
@@ -124,33 +131,89 @@ pub fn del_mdata_user_permissions(
 ) -> Box<CoreFuture<()>> {
     let state = (0, version);
     let client = client.clone();
+    let policy = client.retry_policy();
 
     future::loop_fn(state, move |(attempts, version)| {
+        let client2 = client.clone();
+
         client
             .del_mdata_user_permissions(name, tag, user, version)
             .map(|_| Loop::Break(()))
             .or_else(move |error| match error {
-                CoreError::RoutingClientError(ClientError::NoSuchKey) => Ok(Loop::Break(())),
+                CoreError::RoutingClientError(ClientError::NoSuchKey) => ok!(Loop::Break(())),
                 CoreError::RoutingClientError(ClientError::InvalidSuccessor(current_version)) => {
-                    if attempts < MAX_ATTEMPTS {
-                        Ok(Loop::Continue((attempts + 1, current_version + 1)))
-                    } else {
-                        Err(error)
-                    }
+                    retry_now(&policy, attempts + 1, current_version + 1, error)
                 }
-                CoreError::RequestTimeout => {
-                    if attempts < MAX_ATTEMPTS {
-                        Ok(Loop::Continue((attempts + 1, version)))
-                    } else {
-                        Err(CoreError::RequestTimeout)
-                    }
+                CoreError::RequestTimeout => retry(&client2, &policy, attempts + 1, version, error),
+                error => err!(error),
+            })
+    })
+    .into_box()
+}
+
+/// Transfers ownership of the mutable data to `new_owner` and tries to recover from errors.
+pub fn change_mdata_owner(
+    client: &impl Client,
+    name: XorName,
+    tag: u64,
+    new_owner: sign::PublicKey,
+    version: u64,
+) -> Box<CoreFuture<()>> {
+    let state = (0, version);
+    let client = client.clone();
+    let policy = client.retry_policy();
+
+    future::loop_fn(state, move |(attempts, version)| {
+        let client2 = client.clone();
+
+        client
+            .change_mdata_owner(name, tag, new_owner, version)
+            .map(|_| Loop::Break(()))
+            .or_else(move |error| match error {
+                CoreError::RoutingClientError(ClientError::InvalidSuccessor(current_version)) => {
+                    retry_now(&policy, attempts + 1, current_version + 1, error)
                 }
-                error => Err(error),
+                CoreError::RequestTimeout => retry(&client2, &policy, attempts + 1, version, error),
+                error => err!(error),
             })
     })
     .into_box()
 }
 
+// Continue the retry loop with `state` after the backoff delay, or fail with `error` if there are no
+// attempts left. Used for transient failures such as timeouts.
+fn retry<S: 'static>(
+    client: &impl Client,
+    policy: &RetryPolicy,
+    attempts: u8,
+    state: S,
+    error: CoreError,
+) -> Box<CoreFuture<Loop<(), (u8, S)>>> {
+    if policy.should_retry(attempts) {
+        retry::backoff(client, policy, attempts)
+            .map(move |()| Loop::Continue((attempts, state)))
+            .into_box()
+    } else {
+        err!(error)
+    }
+}
+
+// Continue the retry loop with `state` straight away, or fail with `error` if there are no attempts
+// left. Used when the request has been corrected after a conflict reported by the network, so
+// there's nothing to wait for.
+fn retry_now<S: 'static>(
+    policy: &RetryPolicy,
+    attempts: u8,
+    state: S,
+    error: CoreError,
+) -> Box<CoreFuture<Loop<(), (u8, S)>>> {
+    if policy.should_retry(attempts) {
+        ok!(Loop::Continue((attempts, state)))
+    } else {
+        err!(error)
+    }
+}
+
 fn update_mdata(client: &impl Client, data: MutableData) -> Box<CoreFuture<()>> {
     let client2 = client.clone();
     let client3 = client.clone();
@@ -306,27 +369,20 @@ pub fn ins_auth_key(
 ) -> Box<CoreFuture<()>> {
     let state = (0, version);
     let client = client.clone();
+    let policy = client.retry_policy();
 
     future::loop_fn(state, move |(attempts, version)| {
+        let client2 = client.clone();
+
         client
             .ins_auth_key(key, version)
             .map(|_| Loop::Break(()))
             .or_else(move |error| match error {
                 CoreError::RoutingClientError(ClientError::InvalidSuccessor(current_version)) => {
-                    if attempts < MAX_ATTEMPTS {
-                        Ok(Loop::Continue((attempts + 1, current_version + 1)))
-                    } else {
-                        Err(error)
-                    }
-                }
-                CoreError::RequestTimeout => {
-                    if attempts < MAX_ATTEMPTS {
-                        Ok(Loop::Continue((attempts + 1, version)))
-                    } else {
-                        Err(CoreError::RequestTimeout)
-                    }
+                    retry_now(&policy, attempts + 1, current_version + 1, error)
                 }
-                error => Err(error),
+                CoreError::RequestTimeout => retry(&client2, &policy, attempts + 1, version, error),
+                error => err!(error),
             })
     })
     .into_box()
@@ -465,11 +521,33 @@ mod tests {
 #[cfg(all(test, feature = "mock-network"))]
 mod tests_with_mock_routing {
     use super::*;
+    use crate::utils;
     use crate::utils::test_utils::random_client;
     use rand;
     use routing::{Action, EntryActions, MutableData};
     use rust_sodium::crypto::sign;
 
+    // Test putting the same idata twice, the second time failing with `DataExists`.
+    #[test]
+    fn put_idata_with_recovery() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            let data = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+            let data2 = data.clone();
+
+            put_idata(client, data).then(move |res| {
+                unwrap!(res);
+
+                client2.with_routing(|routing| {
+                    routing.set_next_mutation_error(Some(ClientError::DataExists))
+                });
+
+                put_idata(&client2, data2)
+            })
+        })
+    }
+
     // Test putting mdata and recovering from errors
     #[test]
     fn put_mdata_with_recovery() {
@@ -763,4 +841,45 @@ mod tests_with_mock_routing {
                 })
         })
     }
+
+    // Test changing the owner of mdata and recovering from errors
+    #[test]
+    fn change_mdata_owner_with_recovery() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            let name = rand::random();
+            let tag = 10_000;
+            let owners = btree_set![unwrap!(client.public_signing_key())];
+            let data = unwrap!(MutableData::new(
+                name,
+                tag,
+                Default::default(),
+                Default::default(),
+                owners,
+            ));
+
+            let new_owner = sign::gen_keypair().0;
+
+            client
+                .put_mdata(data)
+                .then(move |res| {
+                    unwrap!(res);
+                    // change with invalid version
+                    change_mdata_owner(&client2, name, tag, new_owner, 0)
+                })
+                .then(move |res| {
+                    unwrap!(res);
+                    client3.get_mdata_shell(name, tag)
+                })
+                .then(move |res| {
+                    let shell = unwrap!(res);
+                    assert_eq!(*shell.owners(), btree_set![new_owner]);
+                    assert_eq!(shell.version(), 1);
+
+                    Ok::<_, CoreError>(())
+                })
+        })
+    }
 }
@@ -0,0 +1,116 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::errors::CoreError;
+use crate::event_loop::CoreFuture;
+use crate::utils::FutureExt;
+use futures::sync::oneshot;
+use futures::Future;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::mem;
+use std::rc::{Rc, Weak};
+
+/// Limits the number of requests a client has in flight at once. Requests over the limit wait,
+/// in the order they were made, until a slot is released.
+#[derive(Clone, Default)]
+pub struct RequestLimiter {
+    state: Rc<RefCell<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    max: Option<usize>,
+    in_flight: usize,
+    waiters: VecDeque<oneshot::Sender<Slot>>,
+}
+
+/// Permission for a single request to be in flight. The slot is released when this is dropped.
+pub struct Slot {
+    state: Weak<RefCell<State>>,
+}
+
+impl RequestLimiter {
+    /// Set the maximum number of requests in flight, or `None` for no limit. Raising the limit
+    /// immediately lets waiting requests through.
+    pub fn set_max(&self, max: Option<usize>) {
+        self.state.borrow_mut().max = max;
+        dispatch_waiters(&self.state);
+    }
+
+    /// Return the number of requests currently in flight.
+    pub fn in_flight(&self) -> usize {
+        self.state.borrow().in_flight
+    }
+
+    /// Take up a slot if one is free, without waiting.
+    pub fn try_acquire(&self) -> Option<Slot> {
+        let mut state = self.state.borrow_mut();
+        if has_capacity(&state) && state.waiters.is_empty() {
+            state.in_flight += 1;
+            Some(Slot {
+                state: Rc::downgrade(&self.state),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Wait for a slot to be released and take it up.
+    pub fn acquire(&self) -> Box<CoreFuture<Slot>> {
+        let (tx, rx) = oneshot::channel();
+        self.state.borrow_mut().waiters.push_back(tx);
+        dispatch_waiters(&self.state);
+
+        rx.map_err(|_| CoreError::OperationAborted).into_box()
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.upgrade() {
+            state.borrow_mut().in_flight -= 1;
+            dispatch_waiters(&state);
+        }
+    }
+}
+
+fn has_capacity(state: &State) -> bool {
+    state.max.map_or(true, |max| state.in_flight < max)
+}
+
+// Hand free slots over to the waiting requests, oldest first.
+fn dispatch_waiters(state: &Rc<RefCell<State>>) {
+    loop {
+        let waiter = {
+            let mut state = state.borrow_mut();
+            if !has_capacity(&state) {
+                return;
+            }
+
+            match state.waiters.pop_front() {
+                Some(waiter) => {
+                    state.in_flight += 1;
+                    waiter
+                }
+                None => return,
+            }
+        };
+
+        let slot = Slot {
+            state: Rc::downgrade(state),
+        };
+
+        if let Err(slot) = waiter.send(slot) {
+            // The request was dropped while waiting. Release its slot here rather than in
+            // `Slot::drop`, which would recurse into this function.
+            mem::forget(slot);
+            state.borrow_mut().in_flight -= 1;
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Client;
+use crate::errors::CoreError;
+use crate::event_loop::CoreFuture;
+use crate::utils::FutureExt;
+use futures::Future;
+use rand::{self, Rng};
+use std::cmp;
+use std::time::Duration;
+use tokio_core::reactor::Timeout;
+
+/// Policy for retrying failed requests with exponential backoff.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one.
+    pub max_attempts: u8,
+    /// Delay before the first retry. Each further retry doubles it.
+    pub base_delay: Duration,
+    /// Upper bound of the delay between two attempts.
+    pub max_delay: Duration,
+    /// Randomise each delay to between half and all of its value, so clients retrying at the same
+    /// time spread out.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 11,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Return `true` if another attempt may be made after `attempts` failed ones.
+    pub fn should_retry(&self, attempts: u8) -> bool {
+        attempts < self.max_attempts
+    }
+
+    /// Delay before the next attempt after `attempts` failed ones.
+    pub fn delay(&self, attempts: u8) -> Duration {
+        let exponent = u32::from(cmp::min(attempts.saturating_sub(1), 31));
+        let delay = self
+            .base_delay
+            .checked_mul(1 << exponent)
+            .map_or(self.max_delay, |delay| cmp::min(delay, self.max_delay));
+
+        if self.jitter {
+            let millis = duration_as_millis(delay);
+            Duration::from_millis(rand::thread_rng().gen_range(millis / 2, millis + 1))
+        } else {
+            delay
+        }
+    }
+}
+
+/// Wait for the policy's delay after `attempts` failed ones, using the event loop timer.
+pub fn backoff(client: &impl Client, policy: &RetryPolicy, attempts: u8) -> Box<CoreFuture<()>> {
+    let delay = policy.delay(attempts);
+    let inner = client.inner();
+    let timeout = match Timeout::new(delay, &inner.borrow().el_handle) {
+        Ok(timeout) => timeout,
+        Err(err) => {
+            return err!(CoreError::Unexpected(format!(
+                "Timeout create error: {:?}",
+                err
+            )))
+        }
+    };
+
+    timeout
+        .map_err(|err| CoreError::Unexpected(format!("Timeout fire error {:?}", err)))
+        .into_box()
+}
+
+fn duration_as_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test that delays grow exponentially up to the maximum.
+    #[test]
+    fn exponential_delay() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(500),
+            jitter: false,
+        };
+
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(400));
+        assert_eq!(policy.delay(4), Duration::from_millis(500));
+        assert_eq!(policy.delay(255), Duration::from_millis(500));
+
+        assert!(policy.should_retry(4));
+        assert!(!policy.should_retry(5));
+
+        let policy = RetryPolicy {
+            jitter: true,
+            ..policy
+        };
+
+        for attempts in 1..5 {
+            let delay = policy.delay(attempts);
+            assert!(delay >= Duration::from_millis(50));
+            assert!(delay <= Duration::from_millis(500));
+        }
+    }
+}
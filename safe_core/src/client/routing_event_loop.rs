@@ -12,6 +12,7 @@ use crate::event::{CoreEvent, NetworkEvent, NetworkTx};
 use crate::event_loop::{CoreMsg, CoreMsgTx};
 use routing::{Event, MessageId, Response};
 use std::sync::mpsc::Receiver;
+use std::time::Instant;
 
 /// Run the routing event loop - this will receive messages from routing.
 pub fn run<C: Client, T>(
@@ -21,6 +22,8 @@ pub fn run<C: Client, T>(
 ) where
     T: 'static,
 {
+    let started_at = Instant::now();
+
     for it in routing_rx.iter() {
         trace!("Received Routing Event: {:?}", it);
         match it {
@@ -37,6 +40,9 @@ pub fn run<C: Client, T>(
                 if let Err(e) = net_tx.unbounded_send(NetworkEvent::Disconnected) {
                     trace!("Couldn't send NetworkEvent::Disconnected: {:?}", e);
                 }
+                let msg =
+                    CoreMsg::new(move |client: &C, _| super::handle_disconnect(client, started_at));
+                let _ = core_tx.unbounded_send(msg);
                 break;
             }
             x => {
@@ -0,0 +1,40 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+/// Counts of the requests a client has issued to the network, broken down by the type of data
+/// they operate on. Requests served from the cache are not counted as issued, but as cache hits.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// Number of `ImmutableData` GET requests.
+    pub idata_gets: u64,
+    /// Number of `ImmutableData` PUT requests.
+    pub idata_puts: u64,
+    /// Number of `MutableData` read requests, including those for entries, versions and
+    /// permissions.
+    pub mdata_gets: u64,
+    /// Number of `MutableData` PUT requests and mutations of entries, permissions or ownership.
+    pub mdata_mutations: u64,
+    /// Number of account requests, i.e. account info and auth key requests.
+    pub account_requests: u64,
+    /// Number of `ImmutableData` GETs served from the cache.
+    pub cache_hits: u64,
+    /// Number of `ImmutableData` GETs not found in the cache and so sent to the network.
+    pub cache_misses: u64,
+}
+
+impl Stats {
+    /// Total number of read requests.
+    pub fn gets(&self) -> u64 {
+        self.idata_gets + self.mdata_gets
+    }
+
+    /// Total number of data PUT and mutation requests.
+    pub fn mutations(&self) -> u64 {
+        self.idata_puts + self.mdata_mutations
+    }
+}
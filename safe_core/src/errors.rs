@@ -64,6 +64,17 @@ pub enum CoreError {
     ConfigError(config_file_handler::Error),
     /// Io error.
     IoError(io::Error),
+    /// The request was cancelled through a `CancellationToken` or `Client::cancel_request`.
+    RequestCancelled,
+    /// The account can't afford the mutation. Only reported when the balance check is enabled.
+    InsufficientBalance {
+        /// Number of mutations the operation needs.
+        needed: u64,
+        /// Number of mutations still available to the account.
+        available: u64,
+    },
+    /// The connection to the network was lost before the response arrived.
+    ConnectionLost,
 }
 
 impl<'a> From<&'a str> for CoreError {
@@ -191,6 +202,13 @@ impl Debug for CoreError {
                 write!(formatter, "CoreError::ConfigError -> {:?}", error)
             }
             CoreError::IoError(ref error) => write!(formatter, "CoreError::IoError -> {:?}", error),
+            CoreError::RequestCancelled => write!(formatter, "CoreError::RequestCancelled"),
+            CoreError::InsufficientBalance { needed, available } => write!(
+                formatter,
+                "CoreError::InsufficientBalance {{ needed: {}, available: {} }}",
+                needed, available
+            ),
+            CoreError::ConnectionLost => write!(formatter, "CoreError::ConnectionLost"),
         }
     }
 }
@@ -250,6 +268,13 @@ impl Display for CoreError {
             CoreError::RequestTimeout => write!(formatter, "CoreError::RequestTimeout"),
             CoreError::ConfigError(ref error) => write!(formatter, "Config file error: {}", error),
             CoreError::IoError(ref error) => write!(formatter, "Io error: {}", error),
+            CoreError::RequestCancelled => write!(formatter, "Request was cancelled"),
+            CoreError::InsufficientBalance { needed, available } => write!(
+                formatter,
+                "Insufficient balance: {} mutations needed, {} available",
+                needed, available
+            ),
+            CoreError::ConnectionLost => write!(formatter, "Connection to the network was lost"),
         }
     }
 }
@@ -280,6 +305,9 @@ impl Error for CoreError {
             CoreError::RequestTimeout => "Request has timed out",
             CoreError::ConfigError(ref error) => error.description(),
             CoreError::IoError(ref error) => error.description(),
+            CoreError::RequestCancelled => "Request cancelled",
+            CoreError::InsufficientBalance { .. } => "Insufficient balance",
+            CoreError::ConnectionLost => "Connection lost",
         }
     }
 
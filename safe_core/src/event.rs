@@ -48,10 +48,14 @@ pub enum CoreEvent {
     GetMData(Result<MutableData, CoreError>),
     /// Rate limit exeeded
     RateLimitExceeded,
+    /// The request was cancelled before its response arrived
+    RequestCancelled,
+    /// The connection to the network was lost before the response arrived
+    ConnectionLost,
 }
 
 /// Network Events that Client Modules need to deal with.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum NetworkEvent {
     /// The core engine is connected to atleast one peer
     Connected,
@@ -6,15 +6,18 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::cancellation::CancellationToken;
 use crate::client::Client;
 use crate::crypto::shared_secretbox;
+use crate::errors::CoreError;
 use crate::event_loop::CoreFuture;
 use crate::self_encryption_storage::SelfEncryptionStorage;
 use crate::utils::{self, FutureExt};
 use futures::Future;
 use maidsafe_utilities::serialisation::{deserialise, serialise};
-use routing::{ImmutableData, XorName};
+use routing::{ImmutableData, XorName, XOR_NAME_LEN};
 use self_encryption::{DataMap, SelfEncryptor};
+use std::cell::Cell;
 
 #[derive(Serialize, Deserialize)]
 enum DataTypeEncoding {
@@ -56,24 +59,85 @@ pub fn create(
         .into_box()
 }
 
+/// Store `value` on the network, encrypted with a freshly generated key. Returns the name of the
+/// stored `ImmutableData` together with the key, both of which are required to retrieve the value
+/// again via `get_value`.
+pub fn put_encrypted(
+    client: &impl Client,
+    value: &[u8],
+) -> Box<CoreFuture<(XorName, shared_secretbox::Key)>> {
+    let client2 = client.clone();
+    let key = shared_secretbox::gen_key();
+
+    create(client, value, Some(key.clone()))
+        .and_then(move |data| {
+            let name = *data.name();
+            client2.put_idata(data).map(move |_| (name, key))
+        })
+        .into_box()
+}
+
 /// Get the raw bytes from `ImmutableData` created via the `create` function in this module.
 pub fn extract_value(
     client: &impl Client,
     data: &ImmutableData,
     decryption_key: Option<shared_secretbox::Key>,
+) -> Box<CoreFuture<Vec<u8>>> {
+    extract_value_impl(client, data, decryption_key, None, None)
+}
+
+/// Same as `extract_value`, but invokes `progress` with the number of chunks fetched so far and the
+/// total number of chunks after each chunk of the value has been retrieved. The callback is run on
+/// the event loop thread. Values small enough to not be split into chunks report no progress.
+pub fn extract_value_with_progress<F>(
+    client: &impl Client,
+    data: &ImmutableData,
+    decryption_key: Option<shared_secretbox::Key>,
+    progress: F,
+) -> Box<CoreFuture<Vec<u8>>>
+where
+    F: FnMut(usize, usize) + 'static,
+{
+    extract_value_impl(client, data, decryption_key, Some(Box::new(progress)), None)
+}
+
+fn extract_value_impl(
+    client: &impl Client,
+    data: &ImmutableData,
+    decryption_key: Option<shared_secretbox::Key>,
+    progress: Option<Box<FnMut(usize, usize)>>,
+    cancellation: Option<CancellationToken>,
 ) -> Box<CoreFuture<Vec<u8>>> {
     let client = client.clone();
 
     unpack(client.clone(), data)
         .and_then(move |value| {
-            let data_map = if let Some(key) = decryption_key {
+            let data_map: DataMap = if let Some(key) = decryption_key {
                 let plain_text = utils::symmetric_decrypt(&value, &key)?;
                 deserialise(&plain_text)?
             } else {
                 deserialise(&value)?
             };
 
-            let storage = SelfEncryptionStorage::new(client);
+            let mut storage = if let Some(mut progress) = progress {
+                let total = match data_map {
+                    DataMap::Chunks(ref chunks) => chunks.len(),
+                    DataMap::Content(_) | DataMap::None => 0,
+                };
+                let fetched = Cell::new(0);
+
+                SelfEncryptionStorage::with_get_hook(client, move || {
+                    let count = fetched.get() + 1;
+                    fetched.set(count);
+                    progress(count, total);
+                })
+            } else {
+                SelfEncryptionStorage::new(client)
+            };
+            if let Some(token) = cancellation {
+                storage.set_cancellation_token(token);
+            }
+
             Ok(SelfEncryptor::new(storage, data_map)?)
         })
         .and_then(|self_encryptor| {
@@ -98,6 +162,100 @@ pub fn get_value(
         .into_box()
 }
 
+/// Same as `get_value`, but reports the download progress via `progress`. See
+/// `extract_value_with_progress` for details.
+pub fn get_value_with_progress<F>(
+    client: &impl Client,
+    name: &XorName,
+    decryption_key: Option<shared_secretbox::Key>,
+    progress: F,
+) -> Box<CoreFuture<Vec<u8>>>
+where
+    F: FnMut(usize, usize) + 'static,
+{
+    let client2 = client.clone();
+    client
+        .get_idata(*name)
+        .and_then(move |data| {
+            extract_value_with_progress(&client2, &data, decryption_key, progress)
+        })
+        .into_box()
+}
+
+/// Same as `get_value`, but the whole retrieval can be aborted through `token`. Once it's
+/// cancelled, the chunk requests in flight are cancelled, no further chunks are requested and the
+/// returned future fails with `CoreError::RequestCancelled`.
+pub fn get_value_cancellable(
+    client: &impl Client,
+    name: &XorName,
+    decryption_key: Option<shared_secretbox::Key>,
+    token: CancellationToken,
+) -> Box<CoreFuture<Vec<u8>>> {
+    if token.is_cancelled() {
+        return err!(CoreError::RequestCancelled);
+    }
+
+    let client2 = client.clone();
+    let token2 = token.clone();
+    let (msg_id, future) = client.get_idata_with_id(*name);
+
+    token
+        .guard_request(client, msg_id, future)
+        .and_then(move |data| {
+            extract_value_impl(&client2, &data, decryption_key, None, Some(token2))
+        })
+        .into_box()
+}
+
+/// Get the names of all the `ImmutableData` the value stored under `name` by `create` is made of:
+/// `name` itself, the chunks of any intermediate data maps and the chunks of the value.
+pub fn chunk_names(
+    client: &impl Client,
+    name: &XorName,
+    decryption_key: Option<shared_secretbox::Key>,
+) -> Box<CoreFuture<Vec<XorName>>> {
+    let client2 = client.clone();
+    let name = *name;
+
+    client
+        .get_idata(name)
+        .and_then(move |data| unpack_with_names(client2, &data, vec![name]))
+        .and_then(move |(value, mut names)| {
+            let data_map: DataMap = if let Some(key) = decryption_key {
+                let plain_text = utils::symmetric_decrypt(&value, &key)?;
+                deserialise(&plain_text)?
+            } else {
+                deserialise(&value)?
+            };
+
+            names.extend(data_map_chunk_names(&data_map)?);
+            Ok(names)
+        })
+        .into_box()
+}
+
+/// Get the names under which the chunks of the given data map are stored. Content kept inline in
+/// the data map has no chunks.
+pub fn data_map_chunk_names(data_map: &DataMap) -> Result<Vec<XorName>, CoreError> {
+    match *data_map {
+        DataMap::Chunks(ref chunks) => chunks
+            .iter()
+            .map(|chunk| {
+                if chunk.hash.len() != XOR_NAME_LEN {
+                    return Err(CoreError::Unexpected(
+                        "Chunk hash is incorrect size.".to_owned(),
+                    ));
+                }
+
+                let mut name = [0u8; XOR_NAME_LEN];
+                name.clone_from_slice(&chunk.hash);
+                Ok(XorName(name))
+            })
+            .collect(),
+        DataMap::Content(_) | DataMap::None => Ok(Vec::new()),
+    }
+}
+
 // TODO: consider rewriting these two function to not use recursion.
 
 fn pack(client: impl Client, value: Vec<u8>) -> Box<CoreFuture<ImmutableData>> {
@@ -122,9 +280,23 @@ fn pack(client: impl Client, value: Vec<u8>) -> Box<CoreFuture<ImmutableData>> {
 }
 
 fn unpack(client: impl Client, data: &ImmutableData) -> Box<CoreFuture<Vec<u8>>> {
+    unpack_with_names(client, data, Vec::new())
+        .map(|(value, _)| value)
+        .into_box()
+}
+
+// Same as `unpack`, but also collects the names of the chunks of all the intermediate data maps
+// into `names`.
+fn unpack_with_names(
+    client: impl Client,
+    data: &ImmutableData,
+    mut names: Vec<XorName>,
+) -> Box<CoreFuture<(Vec<u8>, Vec<XorName>)>> {
     match fry!(deserialise(data.value())) {
-        DataTypeEncoding::Serialised(value) => ok!(value),
+        DataTypeEncoding::Serialised(value) => ok!((value, names)),
         DataTypeEncoding::DataMap(data_map) => {
+            names.extend(fry!(data_map_chunk_names(&data_map)));
+
             let storage = SelfEncryptionStorage::new(client.clone());
             let self_encryptor = fry!(SelfEncryptor::new(storage, data_map));
             let length = self_encryptor.len();
@@ -133,7 +305,7 @@ fn unpack(client: impl Client, data: &ImmutableData) -> Box<CoreFuture<Vec<u8>>>
                 .map_err(From::from)
                 .and_then(move |serialised_data| {
                     let data = fry!(deserialise(&serialised_data));
-                    unpack(client, &data)
+                    unpack_with_names(client, &data, names)
                 })
                 .into_box()
         }
@@ -144,6 +316,8 @@ fn unpack(client: impl Client, data: &ImmutableData) -> Box<CoreFuture<Vec<u8>>>
 mod tests {
     use super::*;
     use futures::Future;
+    use std::cell::RefCell;
+    use std::rc::Rc;
     use utils;
     use utils::test_utils::{finish, random_client};
 
@@ -172,6 +346,123 @@ mod tests {
         create_and_retrieve(10 * 1024 * 1024)
     }
 
+    // Test storing a value encrypted with a random key and retrieving it.
+    #[test]
+    fn put_encrypted_and_retrieve() {
+        let value = unwrap!(utils::generate_random_vector(1024));
+
+        random_client(move |client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            put_encrypted(client, &value)
+                .join(put_encrypted(client, &value))
+                .then(move |res| {
+                    let ((name0, key0), (name1, key1)) = unwrap!(res);
+
+                    // Every put uses a different key, so the same value isn't deduplicated.
+                    assert_ne!(name0, name1);
+                    assert!(key0 != key1);
+
+                    get_value(&client2, &name0, Some(key0)).map(move |value0| (value0, name1))
+                })
+                .then(move |res| {
+                    let (value0, name1) = unwrap!(res);
+                    assert_eq!(value0, value);
+
+                    get_value(&client3, &name1, None)
+                })
+                .then(|res| {
+                    // Can't be retrieved without the key.
+                    assert!(res.is_err());
+                    finish()
+                })
+        })
+    }
+
+    // Test that retrieving a value reports progress after every fetched chunk.
+    #[test]
+    fn retrieve_with_progress() {
+        let value = unwrap!(utils::generate_random_vector(2 * 1024 * 1024));
+
+        random_client(move |client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            let reports = Rc::new(RefCell::new(Vec::new()));
+            let reports2 = Rc::clone(&reports);
+
+            create(client, &value.clone(), None)
+                .then(move |res| {
+                    let data = unwrap!(res);
+                    let data_name = *data.name();
+                    client2.put_idata(data).map(move |_| data_name)
+                })
+                .then(move |res| {
+                    let data_name = unwrap!(res);
+                    get_value_with_progress(&client3, &data_name, None, move |fetched, total| {
+                        reports2.borrow_mut().push((fetched, total))
+                    })
+                })
+                .then(move |res| {
+                    assert_eq!(unwrap!(res), value);
+
+                    let reports = reports.borrow();
+                    assert!(!reports.is_empty());
+
+                    let (_, total) = reports[0];
+                    for (index, &(fetched, reported_total)) in reports.iter().enumerate() {
+                        assert_eq!(fetched, index + 1);
+                        assert_eq!(reported_total, total);
+                    }
+                    assert_eq!(reports.len(), total);
+
+                    finish()
+                })
+        })
+    }
+
+    // Test retrieving a value with a cancellation token, before and after cancelling it.
+    #[test]
+    fn retrieve_cancellable() {
+        let value = unwrap!(utils::generate_random_vector(2 * 1024 * 1024));
+
+        random_client(move |client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            let token = CancellationToken::new();
+            let token2 = token.clone();
+
+            create(client, &value.clone(), None)
+                .then(move |res| {
+                    let data = unwrap!(res);
+                    let data_name = *data.name();
+                    client2.put_idata(data).map(move |_| data_name)
+                })
+                .then(move |res| {
+                    let data_name = unwrap!(res);
+                    get_value_cancellable(&client3, &data_name, None, token)
+                        .map(move |retrieved| (retrieved, data_name))
+                })
+                .then(move |res| {
+                    let (retrieved, data_name) = unwrap!(res);
+                    assert_eq!(retrieved, value);
+
+                    token2.cancel();
+                    get_value_cancellable(&client4, &data_name, None, token2)
+                })
+                .then(|res| {
+                    match res {
+                        Err(CoreError::RequestCancelled) => (),
+                        res => panic!("Unexpected result: {:?}", res),
+                    }
+                    finish()
+                })
+        })
+    }
+
     fn create_and_retrieve(size: usize) {
         let value = unwrap!(utils::generate_random_vector(size));
 
@@ -281,3 +572,94 @@ mod tests {
         }
     }
 }
+
+#[cfg(all(test, feature = "mock-network"))]
+mod tests_with_mock_routing {
+    use super::*;
+    use crate::client::retry::{self, RetryPolicy};
+    use crate::utils::test_utils::random_client;
+    use lru_cache::LruCache;
+    use routing::{Request, Response};
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
+
+    // Test cancelling a retrieval while its chunks are being fetched.
+    // The chunk requests in flight should be cancelled and no further chunks requested.
+    #[test]
+    fn retrieve_cancelled_midway() {
+        let value = unwrap!(utils::generate_random_vector(2 * 1024 * 1024));
+
+        random_client(move |client| {
+            let client2 = client.clone();
+
+            let token = CancellationToken::new();
+            let requests = Rc::new(Cell::new(0));
+            let requests2 = Rc::clone(&requests);
+            let requests3 = Rc::clone(&requests);
+            let delay = RetryPolicy {
+                max_attempts: 1,
+                base_delay: Duration::from_millis(500),
+                max_delay: Duration::from_millis(500),
+                jitter: false,
+            };
+
+            create(client, &value, None)
+                .and_then(move |data| {
+                    let data_name = *data.name();
+                    client2.put_idata(data).map(move |()| (client2, data_name))
+                })
+                .and_then(move |(client, data_name)| {
+                    // Make sure the chunks are fetched from the network.
+                    client.set_data_cache(Box::new(LruCache::<XorName, ImmutableData>::new(10)));
+
+                    client.with_routing(|routing| {
+                        routing.set_request_hook(move |req| {
+                            if let Request::GetIData { .. } = *req {
+                                requests2.set(requests2.get() + 1);
+                            }
+                            None
+                        });
+
+                        // Answer the data map straight away, but hold the chunks back.
+                        routing.set_response_delay_hook(move |res| match *res {
+                            Response::GetIData {
+                                res: Ok(ref data), ..
+                            } if *data.name() == data_name => 0,
+                            _ => 3000,
+                        });
+                    });
+
+                    let started_at = Instant::now();
+                    let retrieval = get_value_cancellable(&client, &data_name, None, token.clone())
+                        .then(move |res| {
+                            match res {
+                                Err(CoreError::RequestCancelled) => (),
+                                res => panic!("Unexpected result: {:?}", res),
+                            }
+                            assert!(started_at.elapsed() < Duration::from_secs(2));
+                            Ok(())
+                        });
+
+                    let client2 = client.clone();
+                    let cancel = retry::backoff(&client, &delay, 1).map(move |()| {
+                        assert!(client2.pending_requests() > 0);
+                        token.cancel();
+                        assert_eq!(client2.pending_requests(), 0);
+                        requests3.get()
+                    });
+
+                    retrieval
+                        .join(cancel)
+                        .and_then(move |((), count)| {
+                            retry::backoff(&client, &delay, 1).map(move |()| (client, count))
+                        })
+                        .map(move |(client, count)| {
+                            // The data map and at least one chunk were requested, and nothing since.
+                            assert!(count > 1);
+                            assert_eq!(requests.get(), count);
+                            assert_eq!(client.pending_requests(), 0);
+                        })
+                })
+        })
+    }
+}
@@ -95,6 +95,8 @@ pub use ffi::*;
 #[macro_use]
 pub mod utils;
 
+/// Cancellation of groups of requests.
+pub mod cancellation;
 /// Client trait and related constants.
 pub mod client;
 /// Config file handling.
@@ -115,7 +117,11 @@ pub mod self_encryption_storage;
 mod errors;
 mod event;
 
-pub use self::client::{mdata_info, recovery, Client, ClientKeys, MDataInfo};
+pub use self::cancellation::CancellationToken;
+pub use self::client::{
+    mdata_info, recovery, Client, ClientConfig, ClientKeys, ConnectionState, DataCache, MDataInfo,
+    PlannedOp, RetryPolicy, Stats,
+};
 #[cfg(feature = "mock-network")]
 pub use self::client::{mock_vault_path, MockRouting};
 pub use self::errors::CoreError;
@@ -81,6 +81,11 @@ impl File {
         &self.user_metadata
     }
 
+    /// Return `true` if this file was modified more recently than `other`.
+    pub fn is_newer_than(&self, other: &File) -> bool {
+        self.modified > other.modified
+    }
+
     /// Set the data-map name of the File
     pub fn set_data_map_name(&mut self, datamap_name: XorName) {
         self.data_map_name = datamap_name;
@@ -140,6 +145,7 @@ fn convert_date_time(sec: i64, nsec: u32) -> Result<DateTime<Utc>, NfsError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Duration;
     use maidsafe_utilities::serialisation::{deserialise, serialise};
 
     // Test that serialising and deserialising a file restores the original file.
@@ -150,4 +156,17 @@ mod tests {
         let obj_after = unwrap!(deserialise(&serialised_data));
         assert_eq!(obj_before, obj_after);
     }
+
+    // Test comparing files by modification time.
+    #[test]
+    fn compare() {
+        let file0 = File::new(Vec::new());
+
+        let mut file1 = file0.clone();
+        file1.set_modified_time(*file0.modified_time() + Duration::seconds(1));
+
+        assert!(file1.is_newer_than(&file0));
+        assert!(!file0.is_newer_than(&file1));
+        assert!(!file0.is_newer_than(&file0));
+    }
 }
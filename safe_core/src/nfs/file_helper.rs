@@ -9,12 +9,14 @@
 use crate::client::{Client, MDataInfo};
 use crate::crypto::shared_secretbox;
 use crate::errors::CoreError;
-use crate::nfs::{File, Mode, NfsError, NfsFuture, Reader, Writer};
+use crate::immutable_data;
+use crate::nfs::{data_map, File, Mode, NfsError, NfsFuture, Reader, Writer};
 use crate::self_encryption_storage::SelfEncryptionStorage;
 use crate::utils::FutureExt;
-use futures::{Future, IntoFuture};
+use futures::{future, Future, IntoFuture};
 use maidsafe_utilities::serialisation::{deserialise, serialise};
-use routing::{ClientError, EntryActions};
+use routing::{ClientError, EntryActions, EntryError, XorName};
+use std::collections::HashSet;
 
 /// Enum specifying which version should be used in places where a version is required.
 #[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
@@ -25,7 +27,9 @@ pub enum Version {
     Custom(u64),
 }
 
-/// Insert the file into the directory.
+/// Insert the file into the directory. If a deleted file left its entry behind under the same
+/// name, the entry is reused at its next version. Fails with `FileExists` if the directory already
+/// has a live file with the same name.
 pub fn insert<S>(client: impl Client, parent: MDataInfo, name: S, file: &File) -> Box<NfsFuture<()>>
 where
     S: AsRef<str>,
@@ -33,23 +37,72 @@ where
     let name = name.as_ref();
     trace!("Inserting file with name '{}'", name);
 
+    let client2 = client.clone();
+
     serialise(&file)
-        .map_err(From::from)
+        .map_err(CoreError::from)
         .and_then(|encoded| {
             let key = parent.enc_entry_key(name.as_bytes())?;
             let value = parent.enc_entry_value(&encoded)?;
 
             Ok((key, value))
         })
+        .map_err(NfsError::from)
         .into_future()
         .and_then(move |(key, value)| {
-            client.mutate_mdata_entries(
-                parent.name,
-                parent.type_tag,
-                EntryActions::new().ins(key, value, 0).into(),
-            )
+            client
+                .mutate_mdata_entries(
+                    parent.name,
+                    parent.type_tag,
+                    EntryActions::new()
+                        .ins(key.clone(), value.clone(), 0)
+                        .into(),
+                )
+                .then(move |res| match res {
+                    Err(CoreError::RoutingClientError(ClientError::InvalidEntryActions(
+                        ref errors,
+                    ))) if errors.values().any(|error| match *error {
+                        EntryError::EntryExists(_) => true,
+                        _ => false,
+                    }) =>
+                    {
+                        reuse_deleted_entry(client2, parent, key, value)
+                    }
+                    res => future::result(res.map_err(NfsError::from)).into_box(),
+                })
+        })
+        .into_box()
+}
+
+// Overwrite the entry under `key` with `value` if it was left behind by a deleted file, i.e. its
+// content is empty, and fail with `FileExists` otherwise.
+fn reuse_deleted_entry(
+    client: impl Client,
+    parent: MDataInfo,
+    key: Vec<u8>,
+    value: Vec<u8>,
+) -> Box<NfsFuture<()>> {
+    let client2 = client.clone();
+
+    client
+        .get_mdata_value(parent.name, parent.type_tag, key.clone())
+        .map_err(NfsError::from)
+        .and_then(move |current| {
+            if !current.content.is_empty() {
+                return err!(NfsError::FileExists);
+            }
+
+            client2
+                .mutate_mdata_entries(
+                    parent.name,
+                    parent.type_tag,
+                    EntryActions::new()
+                        .update(key, value, current.entry_version + 1)
+                        .into(),
+                )
+                .map_err(NfsError::from)
+                .into_box()
         })
-        .map_err(From::from)
         .into_box()
 }
 
@@ -75,6 +128,131 @@ where
         .into_box()
 }
 
+/// Copy a file from `src_parent` into `dst_parent`, optionally under `new_name`, and return the
+/// copied file.
+///
+/// The copy shares its content chunks with the original, so the content is not duplicated on the
+/// network. Only the data map is stored again, encrypted with the destination directory's key (if
+/// any), so the copy is read with that key like any other file in the destination. Fails with
+/// `FileExists` if the destination already contains a file with that name.
+pub fn copy<S>(
+    client: impl Client,
+    src_parent: MDataInfo,
+    name: S,
+    dst_parent: MDataInfo,
+    new_name: Option<String>,
+) -> Box<NfsFuture<File>>
+where
+    S: AsRef<str>,
+{
+    let name = name.as_ref();
+    trace!("Copying file with name '{}'", name);
+
+    let dst_name = new_name.unwrap_or_else(|| name.to_string());
+    let src_enc_key = src_parent.enc_key().cloned();
+    let dst_enc_key = dst_parent.enc_key().cloned();
+    let client2 = client.clone();
+    let client3 = client.clone();
+
+    fetch(client.clone(), src_parent, name)
+        .and_then(move |(_, file)| {
+            data_map::get(&client, file.data_map_name(), src_enc_key)
+                .map(move |data_map| (data_map, file))
+        })
+        .and_then(move |(data_map, mut file)| {
+            data_map::put(&client2, &data_map, dst_enc_key).map(move |data_map_name| {
+                file.set_data_map_name(data_map_name);
+                file
+            })
+        })
+        .and_then(move |file| insert(client3, dst_parent, dst_name, &file).map(move |()| file))
+        .into_box()
+}
+
+/// Fold `f` over all the files in the directory, passing each file together with its name.
+/// Deleted entries are skipped. Directories are flat, so nested paths are simply file names
+/// containing separators and are visited like any other file.
+pub fn fold<T, F>(client: impl Client, parent: MDataInfo, init: T, mut f: F) -> Box<NfsFuture<T>>
+where
+    T: 'static,
+    F: FnMut(T, &str, &File) -> T + 'static,
+{
+    trace!("Folding over files in {:?}", parent.name);
+
+    client
+        .list_mdata_entries(parent.name, parent.type_tag)
+        .map_err(NfsError::from)
+        .and_then(move |entries| {
+            let mut acc = init;
+
+            for (key, value) in entries {
+                // Deleted files leave an entry with empty content behind.
+                if value.content.is_empty() {
+                    continue;
+                }
+
+                let name = String::from_utf8(parent.decrypt(&key)?)
+                    .map_err(|err| NfsError::Unexpected(format!("Invalid file name: {}", err)))?;
+                let file = deserialise(&parent.decrypt(&value.content)?)?;
+
+                acc = f(acc, &name, &file);
+            }
+
+            Ok(acc)
+        })
+        .into_box()
+}
+
+/// Sum the sizes of all the files in the directory. Directories are flat, so this is the total size
+/// of the whole tree below `parent`. Deleted files don't count.
+pub fn total_size(client: impl Client, parent: MDataInfo) -> Box<NfsFuture<u64>> {
+    fold(client, parent, 0, |size, _, file| size + file.size())
+}
+
+/// Collect the names of all the `ImmutableData` chunks referenced by the files in the directory,
+/// i.e. both the chunks of the files' content and those storing their data maps. Directories are
+/// flat, so this covers the whole tree below `parent`. A maintenance tool can compare the result
+/// against a known set of uploaded chunks to find orphaned ones.
+pub fn collect_chunk_references(
+    client: impl Client,
+    parent: MDataInfo,
+) -> Box<NfsFuture<HashSet<XorName>>> {
+    trace!("Collecting chunk references in {:?}", parent.name);
+
+    let enc_key = parent.enc_key().cloned();
+
+    fold(client.clone(), parent, Vec::new(), |mut names, _, file| {
+        names.push(*file.data_map_name());
+        names
+    })
+    .and_then(move |names| {
+        let futures: Vec<_> = names
+            .into_iter()
+            .map(move |name| {
+                let content_chunks =
+                    data_map::get(&client, &name, enc_key.clone()).and_then(|data_map| {
+                        immutable_data::data_map_chunk_names(&data_map).map_err(NfsError::from)
+                    });
+                let data_map_chunks = immutable_data::chunk_names(&client, &name, enc_key.clone())
+                    .map_err(NfsError::from);
+
+                content_chunks.join(data_map_chunks)
+            })
+            .collect();
+
+        future::join_all(futures)
+    })
+    .map(|names| {
+        names
+            .into_iter()
+            .flat_map(|(content_chunks, data_map_chunks)| {
+                content_chunks.into_iter().chain(data_map_chunks)
+            })
+            .collect()
+    })
+    .into_box()
+}
+
 /// Return a Reader for reading the file contents.
 pub fn read<C: Client>(
     client: C,
@@ -90,6 +268,118 @@ pub fn read<C: Client>(
     )
 }
 
+/// Read the whole content of the file.
+pub fn read_content<C: Client>(
+    client: C,
+    file: &File,
+    encryption_key: Option<shared_secretbox::Key>,
+) -> Box<NfsFuture<Vec<u8>>> {
+    read(client, file, encryption_key)
+        .and_then(|reader| {
+            let size = reader.size();
+            reader.read(0, size)
+        })
+        .into_box()
+}
+
+/// Read `len` bytes of the file's content starting at `offset`. Only the chunks covering the
+/// range are fetched. Fails with `InvalidRange` if the range extends past the end of the file.
+pub fn read_content_range<C: Client>(
+    client: C,
+    file: &File,
+    encryption_key: Option<shared_secretbox::Key>,
+    offset: u64,
+    len: u64,
+) -> Box<NfsFuture<Vec<u8>>> {
+    read(client, file, encryption_key)
+        .and_then(move |reader| reader.read(offset, len))
+        .into_box()
+}
+
+/// Return `true` if both files have the same content, regardless of their timestamps and user
+/// metadata. Data maps stored encrypted get a random nonce, so equal content doesn't imply equal
+/// data-map names: unless the names match, both data maps are fetched, decrypted with
+/// `encryption_key` and compared.
+pub fn content_eq(
+    client: impl Client,
+    file: &File,
+    other: &File,
+    encryption_key: Option<shared_secretbox::Key>,
+) -> Box<NfsFuture<bool>> {
+    if file.size() != other.size() {
+        return future::ok(false).into_box();
+    }
+    if file.data_map_name() == other.data_map_name() {
+        return future::ok(true).into_box();
+    }
+
+    data_map::get(&client, file.data_map_name(), encryption_key.clone())
+        .join(data_map::get(
+            &client,
+            other.data_map_name(),
+            encryption_key,
+        ))
+        .map(|(data_map, other_data_map)| data_map == other_data_map)
+        .into_box()
+}
+
+/// Write `content` as the file's content and store the file in the directory, either inserting a
+/// new file or updating the existing one (a previously deleted one included), and return the
+/// stored file. Metadata of an existing file is preserved.
+pub fn write_content<C, S>(
+    client: C,
+    parent: MDataInfo,
+    name: S,
+    content: Vec<u8>,
+) -> Box<NfsFuture<File>>
+where
+    C: Client,
+    S: AsRef<str>,
+{
+    let name = name.as_ref().to_string();
+    trace!("Writing content of file with name '{}'", name);
+
+    let key = fry!(parent.enc_entry_key(name.as_bytes()));
+
+    let client2 = client.clone();
+    let client3 = client.clone();
+    let parent2 = parent.clone();
+
+    client
+        .get_mdata_value(parent.name, parent.type_tag, key)
+        .then(move |res| match res {
+            Ok(value) => {
+                // Deleted files leave an entry with empty content behind.
+                let file = if value.content.is_empty() {
+                    File::new(Vec::new())
+                } else {
+                    deserialise(&parent.decrypt(&value.content)?)?
+                };
+                Ok((Some(value.entry_version + 1), file))
+            }
+            Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)) => {
+                Ok((None, File::new(Vec::new())))
+            }
+            Err(error) => Err(NfsError::from(error)),
+        })
+        .and_then(move |(version, file)| {
+            let enc_key = parent2.enc_key().cloned();
+            write(client2, file, Mode::Overwrite, enc_key)
+                .and_then(move |writer| writer.write(&content).and_then(move |()| writer.close()))
+                .map(move |file| (version, file, parent2))
+        })
+        .and_then(move |(version, file, parent)| {
+            let fut = match version {
+                Some(version) => update(client3, parent, name, &file, Version::Custom(version))
+                    .map(|_| ())
+                    .into_box(),
+                None => insert(client3, parent, name, &file),
+            };
+            fut.map(move |()| file)
+        })
+        .into_box()
+}
+
 /// Delete a file from the directory.
 ///
 /// If `version` is `Version::GetNext`, the current version is first retrieved from the network, and
@@ -7,14 +7,14 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::client::core_client::CoreClient;
-use crate::client::MDataInfo;
+use crate::client::{Client, MDataInfo};
 use crate::crypto::shared_secretbox;
 use crate::errors::CoreError;
 use crate::nfs::file_helper::{self, Version};
 use crate::nfs::reader::Reader;
 use crate::nfs::writer::Writer;
 use crate::nfs::{create_dir, File, Mode, NfsError, NfsFuture};
-use crate::utils::test_utils::random_client;
+use crate::utils::test_utils::{random_client, setup_client};
 use crate::utils::FutureExt;
 use crate::DIR_TAG;
 use futures::future::{self, Loop};
@@ -185,6 +185,154 @@ fn file_read() {
     });
 }
 
+// Test reading the whole content of a file and a range of it, including a range past its end.
+#[test]
+fn file_read_content() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+
+        create_test_file(client)
+            .then(move |res| {
+                let (dir, file) = unwrap!(res);
+                file_helper::read_content(c2, &file, dir.enc_key().cloned())
+                    .map(move |content| (dir, file, content))
+            })
+            .then(move |res| {
+                let (dir, file, content) = unwrap!(res);
+                assert_eq!(content, vec![0u8; ORIG_SIZE]);
+
+                file_helper::read_content_range(c3, &file, dir.enc_key().cloned(), 10, 20)
+                    .map(move |content| (dir, file, content))
+            })
+            .then(move |res| {
+                let (dir, file, content) = unwrap!(res);
+                assert_eq!(content, vec![0u8; 20]);
+
+                file_helper::read_content_range(
+                    c4,
+                    &file,
+                    dir.enc_key().cloned(),
+                    ORIG_SIZE as u64 - 1,
+                    2,
+                )
+            })
+            .then(|res| {
+                match res {
+                    Err(NfsError::InvalidRange) => (),
+                    res => panic!("Unexpected result: {:?}", res),
+                }
+                Ok::<_, NfsError>(())
+            })
+    });
+}
+
+// Test that inserting a file under a name which is already taken fails.
+#[test]
+fn file_insert_existing() {
+    random_client(|client| {
+        let c2 = client.clone();
+
+        create_test_file(client)
+            .then(move |res| {
+                let (dir, file) = unwrap!(res);
+                file_helper::insert(c2, dir, "hello.txt", &file)
+            })
+            .then(|res| {
+                match res {
+                    Err(NfsError::FileExists) => (),
+                    res => panic!("Unexpected result: {:?}", res),
+                }
+                Ok::<_, NfsError>(())
+            })
+    });
+}
+
+// Test inserting a file under the name of a deleted one.
+// The entry left behind by the deleted file should be reused at its next version.
+#[test]
+fn file_delete_then_insert() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+
+        create_test_file(client)
+            .then(move |res| {
+                let (dir, file) = unwrap!(res);
+                file_helper::delete(c2, dir.clone(), "hello.txt", Version::GetNext)
+                    .map(move |version| (version, dir, file))
+            })
+            .then(move |res| {
+                let (version, dir, file) = unwrap!(res);
+                assert_eq!(version, 1);
+                file_helper::insert(c3, dir.clone(), "hello.txt", &file).map(move |()| (dir, file))
+            })
+            .then(move |res| {
+                let (dir, file) = unwrap!(res);
+                file_helper::fetch(c4, dir, "hello.txt")
+                    .map(move |(version, fetched)| (version, fetched, file))
+            })
+            .map(|(version, fetched, file)| {
+                assert_eq!(version, 2);
+                assert_eq!(fetched, file);
+            })
+    });
+}
+
+// Test writing the content of a new file, overwriting it, and writing it again after deletion.
+#[test]
+fn file_write_content() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+        let c5 = client.clone();
+        let c6 = client.clone();
+        let c7 = client.clone();
+
+        let dir = unwrap!(MDataInfo::random_private(DIR_TAG));
+        let dir2 = dir.clone();
+        let dir3 = dir.clone();
+        let dir4 = dir.clone();
+        let dir5 = dir.clone();
+        let dir6 = dir.clone();
+
+        create_dir(client, &dir, btree_map![], btree_map![])
+            .then(move |res| {
+                unwrap!(res);
+                file_helper::write_content(c2, dir2, "hello.txt", vec![1u8; ORIG_SIZE])
+            })
+            .then(move |res| {
+                let _ = unwrap!(res);
+                file_helper::write_content(c3, dir3, "hello.txt", vec![2u8; NEW_SIZE])
+            })
+            .then(move |res| {
+                let file = unwrap!(res);
+                assert_eq!(file.size(), NEW_SIZE as u64);
+                file_helper::delete(c4, dir4, "hello.txt", Version::GetNext)
+            })
+            .then(move |res| {
+                assert_eq!(unwrap!(res), 2);
+                file_helper::write_content(c5, dir5, "hello.txt", vec![3u8; NEW_SIZE])
+            })
+            .then(move |res| {
+                let _ = unwrap!(res);
+                file_helper::fetch(c6, dir6.clone(), "hello.txt")
+                    .map(move |(version, file)| (version, file, dir6))
+            })
+            .then(move |res| {
+                let (version, file, dir) = unwrap!(res);
+                assert_eq!(version, 3);
+                file_helper::read_content(c7, &file, dir.enc_key().cloned())
+            })
+            .map(|content| {
+                assert_eq!(content, vec![3u8; NEW_SIZE]);
+            })
+    });
+}
+
 // Test reading file in chunks.
 #[test]
 fn file_read_chunks() {
@@ -555,6 +703,266 @@ fn file_delete() {
     });
 }
 
+// Test folding over the files of a directory.
+// Deleted files should not be visited.
+#[test]
+fn file_fold() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+
+        create_test_file(client)
+            .then(move |res| {
+                let (dir, file) = unwrap!(res);
+                file_helper::insert(c2, dir.clone(), "world.txt", &file).map(move |_| dir)
+            })
+            .then(move |res| {
+                let dir = unwrap!(res);
+                file_helper::delete(c3, dir.clone(), "hello.txt", Version::GetNext)
+                    .map(move |_| dir)
+            })
+            .then(move |res| {
+                let dir = unwrap!(res);
+                file_helper::fold(c4, dir, (Vec::new(), 0), |(mut names, size), name, file| {
+                    names.push(name.to_string());
+                    (names, size + file.size())
+                })
+            })
+            .map(move |(names, size)| {
+                assert_eq!(names, vec!["world.txt".to_string()]);
+                assert_eq!(size, ORIG_SIZE as u64);
+            })
+    });
+}
+
+// Test summing the sizes of the files in a directory.
+// Files with nested paths count too, while deleted files don't.
+#[test]
+fn file_total_size() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+        let c5 = client.clone();
+
+        create_test_file(client)
+            .then(move |res| {
+                let (dir, _) = unwrap!(res);
+                file_helper::write_content(c2, dir.clone(), "sub/world.txt", vec![1; NEW_SIZE])
+                    .map(move |_| dir)
+            })
+            .then(move |res| {
+                let dir = unwrap!(res);
+                file_helper::total_size(c3, dir.clone()).map(move |size| (dir, size))
+            })
+            .then(move |res| {
+                let (dir, size) = unwrap!(res);
+                assert_eq!(size, (ORIG_SIZE + NEW_SIZE) as u64);
+
+                file_helper::delete(c4, dir.clone(), "hello.txt", Version::GetNext)
+                    .map(move |_| dir)
+            })
+            .then(move |res| {
+                let dir = unwrap!(res);
+                file_helper::total_size(c5, dir)
+            })
+            .map(|size| {
+                assert_eq!(size, NEW_SIZE as u64);
+            })
+    });
+}
+
+// Test copying a file into another directory.
+// The copy should be readable with the destination's key, and copying onto an existing name should
+// fail with `FileExists`.
+#[test]
+fn file_copy() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+        let c5 = client.clone();
+
+        let dst = unwrap!(MDataInfo::random_private(DIR_TAG));
+        let dst2 = dst.clone();
+        let dst3 = dst.clone();
+
+        create_test_file(client)
+            .then(move |res| {
+                let (src, file) = unwrap!(res);
+                create_dir(&c2, &dst, btree_map![], btree_map![]).map(move |()| (src, file))
+            })
+            .then(move |res| {
+                let (src, file) = unwrap!(res);
+                file_helper::copy(
+                    c3,
+                    src.clone(),
+                    "hello.txt",
+                    dst2,
+                    Some("copy.txt".to_string()),
+                )
+                .map(move |copy| {
+                    assert_eq!(copy.size(), file.size());
+                    // The data map is stored again, encrypted with the destination's key.
+                    assert_ne!(copy.data_map_name(), file.data_map_name());
+                    src
+                })
+            })
+            .then(move |res| {
+                let src = unwrap!(res);
+                file_helper::fetch(c4.clone(), dst3.clone(), "copy.txt").and_then(
+                    move |(version, copy)| {
+                        assert_eq!(version, 0);
+                        file_helper::read_content(c4, &copy, dst3.enc_key().cloned())
+                            .map(move |content| (src, dst3, content))
+                    },
+                )
+            })
+            .then(move |res| {
+                let (src, dst, content) = unwrap!(res);
+                assert_eq!(content, vec![0u8; ORIG_SIZE]);
+
+                file_helper::copy(c5, src, "hello.txt", dst, Some("copy.txt".to_string()))
+            })
+            .then(|res| {
+                match res {
+                    Err(NfsError::FileExists) => (),
+                    res => panic!("Unexpected result: {:?}", res),
+                }
+                Ok::<_, NfsError>(())
+            })
+    });
+}
+
+// Test collecting the chunks referenced by the files in a directory.
+// The test file's content is split into three chunks and its data map is small enough to be stored
+// in a single chunk, and deleted files no longer count.
+#[test]
+fn file_collect_chunk_references() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+        let c5 = client.clone();
+
+        create_test_file(client)
+            .then(move |res| {
+                let (dir, file) = unwrap!(res);
+                file_helper::collect_chunk_references(c2, dir.clone())
+                    .map(move |names| (dir, file, names))
+            })
+            .then(move |res| {
+                let (dir, file, names) = unwrap!(res);
+                assert_eq!(names.len(), 4);
+                assert!(names.contains(file.data_map_name()));
+
+                let futures: Vec<_> = names.into_iter().map(|name| c3.get_idata(name)).collect();
+                future::join_all(futures)
+                    .map_err(NfsError::from)
+                    .map(move |_| dir)
+            })
+            .then(move |res| {
+                let dir = unwrap!(res);
+                file_helper::delete(c4, dir.clone(), "hello.txt", Version::GetNext)
+                    .map(move |_| dir)
+            })
+            .then(move |res| {
+                let dir = unwrap!(res);
+                file_helper::collect_chunk_references(c5, dir)
+            })
+            .map(|names| {
+                assert!(names.is_empty());
+            })
+    });
+}
+
+// Test fetching a file through a directory opened from a read capability, by a client which
+// doesn't own the directory.
+#[test]
+fn file_fetch_via_read_capability() {
+    let (token, file) = random_client(|client| {
+        create_test_file(client).map(|(dir, file)| {
+            let token = unwrap!(dir.export_read_capability());
+            (token, file)
+        })
+    });
+
+    setup_client(
+        &(),
+        |el_h, core_tx, net_tx| CoreClient::unregistered(el_h, core_tx, net_tx),
+        move |client| {
+            let c2 = client.clone();
+            let shared = unwrap!(MDataInfo::open_read_capability(&token));
+            let enc_key = shared.enc_key().cloned();
+
+            file_helper::fetch(client.clone(), shared, "hello.txt")
+                .then(move |res| {
+                    let (_, shared_file) = unwrap!(res);
+                    assert_eq!(shared_file, file);
+
+                    file_helper::read_content(c2, &shared_file, enc_key)
+                })
+                .map(|content| {
+                    assert_eq!(content, vec![0u8; ORIG_SIZE]);
+                })
+        },
+    );
+}
+
+// Test comparing the content of files in a private directory.
+// Writing the same bytes twice stores two differently encrypted data maps, which should still
+// compare equal, while different bytes of the same size should not.
+#[test]
+fn file_content_eq() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+        let c5 = client.clone();
+        let c6 = client.clone();
+
+        let dir = unwrap!(MDataInfo::random_private(DIR_TAG));
+        let dir2 = dir.clone();
+        let dir3 = dir.clone();
+        let dir4 = dir.clone();
+        let enc_key = dir.enc_key().cloned();
+        let enc_key2 = enc_key.clone();
+
+        create_dir(client, &dir, btree_map![], btree_map![])
+            .then(move |res| {
+                unwrap!(res);
+                file_helper::write_content(c2, dir2, "a.txt", vec![1u8; ORIG_SIZE])
+            })
+            .then(move |res| {
+                let file_a = unwrap!(res);
+                file_helper::write_content(c3, dir3, "b.txt", vec![1u8; ORIG_SIZE])
+                    .map(move |file_b| (file_a, file_b))
+            })
+            .then(move |res| {
+                let (file_a, file_b) = unwrap!(res);
+                assert_ne!(file_a.data_map_name(), file_b.data_map_name());
+
+                file_helper::content_eq(c4, &file_a, &file_b, enc_key)
+                    .map(move |equal| (equal, file_a))
+            })
+            .then(move |res| {
+                let (equal, file_a) = unwrap!(res);
+                assert!(equal);
+
+                file_helper::write_content(c5, dir4, "c.txt", vec![2u8; ORIG_SIZE])
+                    .map(move |file_c| (file_a, file_c))
+            })
+            .then(move |res| {
+                let (file_a, file_c) = unwrap!(res);
+                file_helper::content_eq(c6, &file_a, &file_c, enc_key2)
+            })
+            .map(|equal| {
+                assert!(!equal);
+            })
+    });
+}
+
 // Test deleting an entry and then re-adding it.
 // We should be able to successfully open and read the re-added file.
 #[test]
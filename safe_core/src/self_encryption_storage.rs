@@ -6,23 +6,71 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{Client, CoreError, FutureExt};
+use super::{CancellationToken, Client, CoreError, CoreFuture, FutureExt};
 use futures::{self, Future};
-use routing::{ImmutableData, XorName, XOR_NAME_LEN};
+use routing::{ImmutableData, MessageId, XorName, XOR_NAME_LEN};
 use self_encryption::{Storage, StorageError};
+use std::cell::RefCell;
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
 
 /// Network storage is the concrete type which self-encryption crate will use
 /// to put or get data from the network.
 pub struct SelfEncryptionStorage<C: Client> {
     client: C,
+    get_hook: Option<Rc<RefCell<Box<FnMut()>>>>,
+    cancellation: Option<CancellationToken>,
 }
 
 impl<C: Client> SelfEncryptionStorage<C> {
     /// Create a new SelfEncryptionStorage instance.
     pub fn new(client: C) -> Self {
-        SelfEncryptionStorage { client }
+        SelfEncryptionStorage {
+            client,
+            get_hook: None,
+            cancellation: None,
+        }
+    }
+
+    /// Create a new SelfEncryptionStorage instance which invokes `hook` on the event loop thread
+    /// every time a chunk has been successfully fetched from the network.
+    pub fn with_get_hook<F>(client: C, hook: F) -> Self
+    where
+        F: FnMut() + 'static,
+    {
+        SelfEncryptionStorage {
+            client,
+            get_hook: Some(Rc::new(RefCell::new(Box::new(hook)))),
+            cancellation: None,
+        }
+    }
+
+    /// Make all subsequent gets and puts fail with `CoreError::RequestCancelled` once `token` is
+    /// cancelled. No further requests are issued after that.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = Some(token);
+    }
+
+    // Issue the request, unless the cancellation token (if any) has already been cancelled.
+    // `request` returns the id of the message it sent, if known, so the token can cancel it.
+    fn guard<T, F>(&self, request: F) -> Box<Future<Item = T, Error = SelfEncryptionStorageError>>
+    where
+        T: 'static,
+        F: FnOnce() -> (Option<MessageId>, Box<CoreFuture<T>>),
+    {
+        match self.cancellation {
+            Some(ref token) if token.is_cancelled() => {
+                Box::new(futures::failed(CoreError::RequestCancelled.into()))
+            }
+            Some(ref token) => match request() {
+                (Some(msg_id), future) => token.guard_request(&self.client, msg_id, future),
+                (None, future) => token.guard(future),
+            }
+            .map_err(From::from)
+            .into_box(),
+            None => request().1.map_err(From::from).into_box(),
+        }
     }
 }
 
@@ -44,17 +92,30 @@ impl<C: Client> Storage for SelfEncryptionStorage<C> {
             XorName(temp)
         };
 
-        self.client
-            .get_idata(name)
-            .map(|data| data.value().clone())
-            .map_err(From::from)
-            .into_box()
+        let get_hook = self.get_hook.clone();
+        let client = self.client.clone();
+
+        self.guard(move || {
+            let (msg_id, future) = client.get_idata_with_id(name);
+            let future = future
+                .map(move |data| {
+                    if let Some(hook) = get_hook {
+                        let mut hook = hook.borrow_mut();
+                        (*hook)();
+                    }
+                    data.value().clone()
+                })
+                .into_box();
+
+            (Some(msg_id), future)
+        })
     }
 
     fn put(&mut self, _: Vec<u8>, data: Vec<u8>) -> Box<Future<Item = (), Error = Self::Error>> {
         trace!("Self encrypt invoked PutIData.");
         let data = ImmutableData::new(data);
-        self.client.put_idata(data).map_err(From::from).into_box()
+        let client = self.client.clone();
+        self.guard(move || (None, client.put_idata(data)))
     }
 }
 
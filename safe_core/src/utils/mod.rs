@@ -20,7 +20,9 @@ use crate::errors::CoreError;
 use maidsafe_utilities::serialisation::{deserialise, serialise};
 use rand::Rng;
 use rust_sodium::crypto::hash::sha512::{self, Digest, DIGESTBYTES};
-use rust_sodium::crypto::secretbox;
+use rust_sodium::crypto::{box_, secretbox};
+use serde::Serialize;
+use std::collections::HashSet;
 
 /// Easily create a BTreeSet.
 #[macro_export]
@@ -94,6 +96,39 @@ pub fn symmetric_decrypt(
         .map_err(|_| CoreError::SymmetricDecipherFailure)
 }
 
+#[derive(Serialize, Deserialize)]
+struct AsymmetricEnc {
+    nonce: [u8; box_::NONCEBYTES],
+    cipher_text: Vec<u8>,
+}
+
+/// Asymmetric encryption of `plain_text` for the owner of `their_pk`, authenticated with
+/// `our_sk`. A random nonce is generated and stored with the cipher text.
+pub fn asymmetric_encrypt(
+    plain_text: &[u8],
+    their_pk: &box_::PublicKey,
+    our_sk: &box_::SecretKey,
+) -> Result<Vec<u8>, CoreError> {
+    let nonce = box_::gen_nonce();
+    let cipher_text = box_::seal(plain_text, &nonce, their_pk, our_sk);
+
+    Ok(serialise(&AsymmetricEnc {
+        nonce: nonce.0,
+        cipher_text,
+    })?)
+}
+
+/// Asymmetric decryption of data encrypted by `asymmetric_encrypt` by the owner of `their_pk`.
+pub fn asymmetric_decrypt(
+    cipher_text: &[u8],
+    their_pk: &box_::PublicKey,
+    our_sk: &box_::SecretKey,
+) -> Result<Vec<u8>, CoreError> {
+    let AsymmetricEnc { nonce, cipher_text } = deserialise::<AsymmetricEnc>(cipher_text)?;
+    box_::open(&cipher_text, &box_::Nonce(nonce), their_pk, our_sk)
+        .map_err(|_| CoreError::AsymmetricDecipherFailure)
+}
+
 /// Generates a `String` from `length` random UTF-8 `char`s.  Note that the NULL character will be
 /// excluded to allow conversion to a `CString` if required, and that the actual `len()` of the
 /// returned `String` will likely be around `4 * length` as most of the randomly-generated `char`s
@@ -142,9 +177,73 @@ pub fn derive_secrets(acc_locator: &[u8], acc_password: &[u8]) -> (Vec<u8>, Vec<
     (password, keyword, pin)
 }
 
+/// Return the size of `value` once serialised for sending to the network. Use this to check data
+/// against the network's size limits before sending it.
+pub fn serialised_size<T: Serialize>(value: &T) -> Result<u64, CoreError> {
+    Ok(serialise(value)?.len() as u64)
+}
+
+/// Estimated strength of a pair of account credentials.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CredentialStrength {
+    /// Easily guessable - should be rejected.
+    Weak,
+    /// Acceptable, but could be improved.
+    Fair,
+    /// Hard to guess.
+    Strong,
+}
+
+// Minimum estimated entropy (in bits) of each credential for the given strength.
+const FAIR_CREDENTIAL_BITS: f64 = 40.0;
+const STRONG_CREDENTIAL_BITS: f64 = 64.0;
+
+/// Estimate the strength of the given account locator and password. Both are secrets, so the
+/// result is determined by the weaker of the two.
+pub fn credential_strength(acc_locator: &str, acc_password: &str) -> CredentialStrength {
+    let bits = estimate_entropy_bits(acc_locator).min(estimate_entropy_bits(acc_password));
+
+    if bits >= STRONG_CREDENTIAL_BITS {
+        CredentialStrength::Strong
+    } else if bits >= FAIR_CREDENTIAL_BITS {
+        CredentialStrength::Fair
+    } else {
+        CredentialStrength::Weak
+    }
+}
+
+// Rough entropy estimate based on the character classes used and the length of the secret.
+// Repeated characters only count for half as they add little to the search space.
+fn estimate_entropy_bits(secret: &str) -> f64 {
+    let mut pool = 0;
+    if secret.chars().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if secret.chars().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if secret.chars().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if secret.chars().any(|c| !c.is_ascii_alphanumeric()) {
+        pool += 33;
+    }
+
+    if pool == 0 {
+        return 0.0;
+    }
+
+    let length = secret.chars().count();
+    let distinct = secret.chars().collect::<HashSet<_>>().len();
+    let effective_length = distinct as f64 + (length - distinct) as f64 / 2.0;
+
+    effective_length * f64::from(pool).log2()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use routing::ImmutableData;
 
     const SIZE: usize = 10;
 
@@ -203,4 +302,75 @@ mod tests {
             assert_eq!(password, keyword);
         }
     }
+
+    // Test asymmetric encryption and that truncated or tampered cipher texts fail to decrypt.
+    #[test]
+    fn asymmetric_encryption() {
+        let (our_pk, our_sk) = box_::gen_keypair();
+        let (their_pk, their_sk) = box_::gen_keypair();
+        let plain_text = unwrap!(generate_random_vector::<u8>(SIZE));
+
+        let cipher_text = unwrap!(asymmetric_encrypt(&plain_text, &their_pk, &our_sk));
+        assert_eq!(
+            unwrap!(asymmetric_decrypt(&cipher_text, &our_pk, &their_sk)),
+            plain_text
+        );
+
+        match asymmetric_decrypt(&cipher_text[..SIZE], &our_pk, &their_sk) {
+            Err(CoreError::EncodeDecodeError(_)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        let mut tampered = cipher_text.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        match asymmetric_decrypt(&tampered, &our_pk, &their_sk) {
+            Err(CoreError::AsymmetricDecipherFailure) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        // Only the intended recipient can decrypt.
+        let (_, other_sk) = box_::gen_keypair();
+        match asymmetric_decrypt(&cipher_text, &our_pk, &other_sk) {
+            Err(CoreError::AsymmetricDecipherFailure) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    // Test that the serialised size matches the size of the actual serialised data.
+    #[test]
+    fn serialised_size_of_data() {
+        let data = ImmutableData::new(unwrap!(generate_random_vector(SIZE * 100)));
+        let size = unwrap!(serialised_size(&data));
+
+        assert_eq!(size, unwrap!(serialise(&data)).len() as u64);
+        assert!(size > (SIZE * 100) as u64);
+    }
+
+    // Test estimating the strength of account credentials.
+    #[test]
+    fn credentials_strength() {
+        assert_eq!(credential_strength("", ""), CredentialStrength::Weak);
+        assert_eq!(
+            credential_strength("aaaaaaaa", "password"),
+            CredentialStrength::Weak
+        );
+        // A strong password doesn't make up for a weak locator.
+        assert_eq!(
+            credential_strength("locator", "c0rrect-H0rse-Battery-St4ple"),
+            CredentialStrength::Weak
+        );
+        assert_eq!(
+            credential_strength("Ocean42", "Tricky7"),
+            CredentialStrength::Fair
+        );
+        assert_eq!(
+            credential_strength("c0rrect-H0rse-Battery", "St4ple!Under#Moonlight"),
+            CredentialStrength::Strong
+        );
+
+        let locator = unwrap!(generate_readable_string(SIZE * 2));
+        let password = unwrap!(generate_readable_string(SIZE * 2));
+        assert!(credential_strength(&locator, &password) >= CredentialStrength::Fair);
+    }
 }
@@ -64,6 +64,19 @@ where
     E: Debug,
 {
     let n = |net_event| panic!("Unexpected NetworkEvent occurred: {:?}", net_event);
+    random_client_with_net_obs(n, r)
+}
+
+/// Create random registered client and run it inside an event loop, passing the network events
+/// to the given observer.
+pub fn random_client_with_net_obs<NetObs, Run, I, T, E>(n: NetObs, r: Run) -> T
+where
+    NetObs: FnMut(NetworkEvent) + 'static,
+    Run: FnOnce(&CoreClient) -> I + Send + 'static,
+    I: IntoFuture<Item = T, Error = E> + 'static,
+    T: Send + 'static,
+    E: Debug,
+{
     let c = |el_h, core_tx, net_tx| {
         let acc_locator = unwrap!(utils::generate_random_string(10));
         let acc_password = unwrap!(utils::generate_random_string(10));